@@ -2,11 +2,16 @@
 
 layout(location = 0) in vec3 vertexPosition_modelspace;
 layout(location = 1) in vec2 vertexUV;
+layout(location = 2) in vec2 vertexLight;
 
 out vec2 texcoord;
+out float brightness;
 uniform mat4 mvp;
 
 void main(void) {
     gl_Position = mvp * vec4(vertexPosition_modelspace, 1.0);
     texcoord = vertexUV;
-}
\ No newline at end of file
+    // each light level below the max darkens by 20%
+    float level = max(vertexLight.x, vertexLight.y);
+    brightness = pow(0.8, (1.0 - level) * 15.0);
+}
@@ -22,27 +22,30 @@
 use std::num::{cos, sin};
 use std::f32::consts::PI;
 
-use glfw;
-
 use input_manager::InputDatas;
 use math::{Mat4, Vec3, Vec2};
-use timer::Timer;
 
-pub static SPEED: f32               = 12f32;
 pub static MOUSE_SPEED: f32         = 0.1f32;
 pub static FOVY: f32                = 45f32;
 pub static ASPECT: f32              = 4f32 / 3f32;
 pub static Z_NEAR: f32              = 0.1f32;
 pub static Z_FAR: f32               = 100f32;
 pub static CONST_RIGHT_ANGLE: f32   = PI / 2f32;
+// how fast the fov reaches its target, per second
+static FOV_EASING: f32              = 10f32;
 
 pub struct Camera {
     priv mat_projection:    Mat4<f32>,
     priv mat_view:          Mat4<f32>,
     priv position:          Vec3<f32>,
+    // position at the previous tick, the view is interpolated between the two
+    priv previous:          Vec3<f32>,
+    priv fov:               f32,
+    // multiplies FOVY, e.g. raised while sprinting
+    priv fov_scale:         f32,
+    priv z_far:             f32,
     priv h_angle:           f32,
     priv v_angle:           f32,
-    priv timer:             Timer,
     priv window_size:       Vec2<f32>
 }
 
@@ -52,49 +55,89 @@ impl Camera {
             mat_projection:     Mat4::perspective(FOVY, ASPECT, Z_NEAR, Z_FAR),
             mat_view:           Mat4::look_at(&Vec3::new(0f32, 0f32, 9f32), &Vec3::new(0f32, 0f32, 0f32), &Vec3::new(0f32, 1f32, 0f32)),
             position:           Vec3::new(0f32, 9f32, 0f32),
+            previous:           Vec3::new(0f32, 9f32, 0f32),
+            fov:                FOVY,
+            fov_scale:          1f32,
+            z_far:              Z_FAR,
             h_angle:            PI,
             v_angle:            0f32,
-            timer:              Timer::new(),
             window_size:        window_size
         }
     }
 
-    pub fn move(&mut self, 
-        input_datas: &InputDatas,
-        dir: &Vec3<f32>,
-        right: &Vec3<f32>,
-        delta_time: f32) -> () {
-
-        for i in input_datas.keys.iter() {
-            match i {
-                &(glfw::Press, glfw::KeyW)     => self.position = self.position.add_vec(&dir.scalar_product(delta_time).scalar_product(SPEED)),
-                &(glfw::Press, glfw::KeyS)   => self.position = self.position.sub_vec(&dir.scalar_product(delta_time).scalar_product(SPEED)),
-                &(glfw::Press, glfw::KeyD)  => self.position = self.position.add_vec(&right.scalar_product(delta_time).scalar_product(SPEED)),
-                &(glfw::Press, glfw::KeyA)   => self.position = self.position.sub_vec(&right.scalar_product(delta_time).scalar_product(SPEED)),
-                _                               => {}
-            }
-        }
+    // one simulation tick, the eyes follow the player
+    pub fn move_to(&mut self, position: &Vec3<f32>) -> () {
+        self.previous = self.position.clone();
+        self.position = position.clone();
     }
 
+    // every frame, the mouse look and the view at `alpha` between the
+    // previous tick and the last one
     pub fn update(&mut self, 
-        input_datas: &InputDatas) -> () {
-        
-        let delta_time = self.timer.delta_time();
+        input_datas: &InputDatas,
+        delta_time: f64,
+        alpha: f32) -> () {
 
         self.h_angle += MOUSE_SPEED * ::std::num::cast(delta_time).unwrap() * (self.window_size.x / 2f32 - input_datas.mouse_position.x);
         self.v_angle += MOUSE_SPEED * ::std::num::cast(delta_time).unwrap() * (self.window_size.y / 2f32 - input_datas.mouse_position.y);
         
-        let dir     = Vec3::new(cos(self.v_angle) * sin(self.h_angle), sin(self.v_angle), cos(self.v_angle) * cos(self.h_angle));
-        let right   = Vec3::new(sin(self.h_angle - CONST_RIGHT_ANGLE), 0f32, cos(self.h_angle - CONST_RIGHT_ANGLE));
+        let target = FOVY * self.fov_scale;
+        if self.fov != target {
+            let t: f32 = ::std::num::cast(delta_time).unwrap();
+            let step = if t * FOV_EASING > 1f32 { 1f32 } else { t * FOV_EASING };
+            self.fov += (target - self.fov) * step;
+            if (target - self.fov).abs() < 0.01f32 {
+                self.fov = target;
+            }
+            self.mat_projection = Mat4::perspective(self.fov, ASPECT, Z_NEAR, self.z_far);
+        }
+
+        let dir     = self.get_direction();
         // let up      = right.cross_product(&dir);
         let up = Vec3::new(0f32, 1f32, 0f32);
-        self.move(input_datas, &dir, &right, ::std::num::cast(delta_time).unwrap());
-        // Free fly = remove / add the next line
-        // self.position.y = 1f32;
-        self.mat_view = Mat4::look_at(&self.position, &self.position.add_vec(&dir), &up);
+        let eye = self.previous.add_vec(&self.position.sub_vec(&self.previous).scalar_product(alpha));
+        self.mat_view = Mat4::look_at(&eye, &eye.add_vec(&dir), &up);
     }
 
     pub fn get_mat(&self) -> Mat4<f32> {
         self.mat_projection.cross_product(&self.mat_view)
     }
+
+    pub fn get_position(&self) -> Vec3<f32> {
+        self.position.clone()
+    }
+
+    // teleports, no interpolation from the old position
+    pub fn set_position(&mut self, position: &Vec3<f32>) -> () {
+        self.position = position.clone();
+        self.previous = position.clone();
+    }
+
+    // (horizontal, vertical)
+    pub fn get_angles(&self) -> (f32, f32) {
+        (self.h_angle, self.v_angle)
+    }
+
+    pub fn set_angles(&mut self, h_angle: f32, v_angle: f32) -> () {
+        self.h_angle = h_angle;
+        self.v_angle = v_angle;
+    }
+
+    pub fn get_direction(&self) -> Vec3<f32> {
+        Vec3::new(cos(self.v_angle) * sin(self.h_angle), sin(self.v_angle), cos(self.v_angle) * cos(self.h_angle))
+    }
+
+    pub fn set_z_far(&mut self, z_far: f32) -> () {
+        self.z_far = z_far;
+        self.mat_projection = Mat4::perspective(self.fov, ASPECT, Z_NEAR, z_far);
+    }
+
+    pub fn get_fov(&self) -> f32 {
+        self.fov
+    }
+
+    // the fov eases toward FOVY * `fov_scale`
+    pub fn set_fov_scale(&mut self, fov_scale: f32) -> () {
+        self.fov_scale = fov_scale;
+    }
 }
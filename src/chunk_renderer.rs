@@ -0,0 +1,142 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::hashmap::HashMap;
+use std::{mem, cast, ptr};
+
+use gl;
+use gl::types::{GLsizeiptr, GLuint, GLint, GLsizei};
+use math::Mat4;
+use shaders;
+use texture_loader::TextureLoader;
+use world::chunk::ChunkPos;
+use world::mesher::{MeshData, Vertex};
+
+struct ChunkMesh {
+    vertices:   GLuint,
+    // (texture index, first vertex, vertices count)
+    batches:    ~[(u32, GLint, GLsizei)]
+}
+
+pub struct ChunkRenderer {
+    priv prog:      GLuint,
+    priv mvp:       GLint,
+    priv tex:       GLint,
+    priv meshes:    HashMap<ChunkPos, ChunkMesh>
+}
+
+impl ChunkRenderer {
+    pub fn new() -> ChunkRenderer {
+        let prog_id = shaders::load_from_file(
+            Path::new("./shaders/cube_vertex_shader.glsl"), 
+            Path::new("./shaders/cube_fragment_shader.glsl")
+        ).unwrap();
+        let mvp_id = unsafe { "mvp".with_c_str(|c_str| gl::GetUniformLocation(prog_id, c_str)) };
+        let tex_id = unsafe { "tex".with_c_str(|c_str| gl::GetUniformLocation(prog_id, c_str)) };
+
+        ChunkRenderer {
+            prog:       prog_id,
+            mvp:        mvp_id,
+            tex:        tex_id,
+            meshes:     HashMap::new()
+        }
+    }
+
+    // replace the mesh of the chunk, all the batches share one vertex buffer
+    pub fn upload(&mut self, position: &ChunkPos, mesh: &MeshData) -> () {
+        self.remove(position);
+        if mesh.batches.is_empty() {
+            return;
+        }
+
+        let mut vertices: ~[Vertex] = ~[];
+        let mut batches = ~[];
+        for b in mesh.batches.iter() {
+            batches.push((b.texture, vertices.len() as GLint, b.vertices.len() as GLsizei));
+            vertices.push_all(b.vertices.as_slice());
+        }
+
+        let mut vbo = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(gl::ARRAY_BUFFER, 
+                (vertices.len() * mem::size_of::<Vertex>()) as GLsizeiptr, 
+                cast::transmute(&vertices[0]), 
+                gl::STATIC_DRAW);
+        }
+        self.meshes.insert(position.clone(), ChunkMesh { vertices: vbo, batches: batches });
+    }
+
+    pub fn remove(&mut self, position: &ChunkPos) -> () {
+        match self.meshes.pop(position) {
+            Some(m) => unsafe { gl::DeleteBuffers(1, &m.vertices); },
+            None    => {}
+        }
+    }
+
+    pub fn contains(&self, position: &ChunkPos) -> bool {
+        self.meshes.contains_key(position)
+    }
+
+    pub fn draw(&self, mvp: &Mat4<f32>, texture_loader: &TextureLoader) -> () {
+        let stride = mem::size_of::<Vertex>() as GLsizei;
+
+        gl::UseProgram(self.prog);
+        unsafe { gl::UniformMatrix4fv(self.mvp, 1, gl::FALSE, &mvp.a1); }
+        gl::ActiveTexture(gl::TEXTURE0);
+        gl::Uniform1i(self.tex, 0);
+        gl::EnableVertexAttribArray(0);
+        gl::EnableVertexAttribArray(1);
+        gl::EnableVertexAttribArray(2);
+
+        for (_, mesh) in self.meshes.iter() {
+            gl::BindBuffer(gl::ARRAY_BUFFER, mesh.vertices);
+            unsafe {
+                gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
+                gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, stride, 
+                    cast::transmute(3 * mem::size_of::<f32>()));
+                // (sky light, block light)
+                gl::VertexAttribPointer(2, 2, gl::FLOAT, gl::FALSE, stride, 
+                    cast::transmute(5 * mem::size_of::<f32>()));
+            }
+            for &(texture, first, count) in mesh.batches.iter() {
+                gl::BindTexture(gl::TEXTURE_2D, texture_loader.get(texture));
+                gl::DrawArrays(gl::TRIANGLES, first, count);
+            }
+        }
+
+        gl::DisableVertexAttribArray(0);
+        gl::DisableVertexAttribArray(1);
+        gl::DisableVertexAttribArray(2);
+    }
+}
+
+impl Drop for ChunkRenderer {
+    fn drop(&mut self) -> () {
+        unsafe {
+            for (_, mesh) in self.meshes.iter() {
+                gl::DeleteBuffers(1, &mesh.vertices);
+            }
+            gl::DeleteProgram(self.prog);
+        }
+    }
+}
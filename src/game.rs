@@ -30,10 +30,12 @@ use glfw_utils;
 use texture_loader;
 use texture_loader::TextureLoader;
 use world::World;
+use world::block;
 use font::Font;
 use perf_metrics::{PerfMetrics, Fps};
 use input_manager::{InputManager, InputDatas};
 use math::Vec2;
+use timer::{Timer, TickClock, DEFAULT_TICK_RATE};
 
 pub struct Game {
     priv window:            glfw::Window,
@@ -42,17 +44,21 @@ pub struct Game {
     priv game_font:         Rc<RefCell<Font>>,
     priv fps:               PerfMetrics<Fps>,
     priv vertex_array:      GLuint,
-    priv input_manager:     InputManager
-
+    priv input_manager:     InputManager,
+    priv clock:             TickClock,
+    priv frame_timer:       Timer
 }
 
 impl Game {
-    pub fn new() -> Game {
+    // `preset_name` is only used when the save does not exist yet
+    pub fn new(preset_name: &str) -> Game {
         let window =            glfw_utils::init();
         let vertex_array =      Game::init_gl();
         let input_manager =     glfw_utils::init_callbacks(&window);
         let tex_loader =        texture_loader::make();
-        let world =             World::new(tex_loader.clone(), Vec2::new(1024f32, 768f32));
+        let blocks =            tex_loader.borrow().with(|loader| block::make(loader));
+        let world =             World::new(tex_loader.clone(), blocks, Vec2::new(1024f32, 768f32), 
+                                    Path::new("./saves/world"), preset_name);
         let font =              Rc::new(RefCell::new(Font::new()));
         let pm =                PerfMetrics::new(Fps::new(), font.clone());
 
@@ -63,7 +69,9 @@ impl Game {
             game_font:          font,
             fps:                pm,
             vertex_array:       vertex_array,
-            input_manager:      input_manager
+            input_manager:      input_manager,
+            clock:              TickClock::new(DEFAULT_TICK_RATE),
+            frame_timer:        Timer::new()
         }
     }
 
@@ -95,6 +103,22 @@ impl Game {
         }
     }
 
+    // P pauses, O runs a single tick while paused, - and = halve and
+    // double the tick rate
+    pub fn handle_clock_keys(&mut self, 
+        inputs: &InputDatas) -> () {
+
+        for k in inputs.keys.iter() {
+            match k {
+                &(glfw::Press, glfw::KeyP)      => { let paused = self.clock.is_paused(); self.clock.set_paused(!paused) },
+                &(glfw::Press, glfw::KeyO)      => self.clock.step(),
+                &(glfw::Press, glfw::KeyMinus)  => { let rate = self.clock.get_tick_rate(); self.clock.set_tick_rate(rate / 2f64) },
+                &(glfw::Press, glfw::KeyEqual)  => { let rate = self.clock.get_tick_rate(); self.clock.set_tick_rate(rate * 2f64) },
+                _                               => {}
+            }
+        }
+    }
+
     pub fn run(&mut self) -> () {
         let mut input_datas = self.input_manager.update(&self.window);
         while !self.window.should_close() {
@@ -105,10 +129,17 @@ impl Game {
             // Clear the screen to black
             gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
 
-            self.world.update(&input_datas);
+            // the simulation runs at the clock's rate, whatever the frame rate
+            let tick_duration = self.clock.get_tick_duration() as f32;
+            for _ in range(0, self.clock.advance()) {
+                self.world.tick(&input_datas, tick_duration);
+            }
+            let frame_time = self.frame_timer.delta_time();
+            self.world.update(&input_datas, frame_time, self.clock.get_alpha());
             self.world.draw();
             input_datas = self.input_manager.update(&self.window);
             self.test_should_close(&input_datas);
+            self.handle_clock_keys(&input_datas);
 
 
             self.fps.frame_end();
@@ -118,6 +149,7 @@ impl Game {
             self.window.swap_buffers();
             check_error();
         }
+        self.world.save();
     }
 }
 
@@ -40,10 +40,12 @@ pub fn init() -> glfw::Window {
 
 pub fn init_callbacks(window: &glfw::Window) -> InputManager {
     let (key_p, key_c) = Chan::new();
+    let (mouse_p, mouse_c) = Chan::new();
 
     window.set_key_callback(~KeyCallbackImpl { key_chan: key_c });
+    window.set_mouse_button_callback(~MouseButtonCallbackImpl { button_chan: mouse_c });
     glfw::set_error_callback(~ErrorContext);
-    InputManager::new(key_p)
+    InputManager::new(key_p, mouse_p)
 }
 
 
@@ -67,3 +69,14 @@ impl glfw::KeyCallback for KeyCallbackImpl {
     }
 }
 
+struct MouseButtonCallbackImpl { button_chan: Chan<(glfw::Action, glfw::MouseButton)> }
+impl glfw::MouseButtonCallback for MouseButtonCallbackImpl {
+    fn call(&self, 
+        _: &glfw::Window, 
+        button: glfw::MouseButton, 
+        action: glfw::Action, 
+        _: glfw::Modifiers) {
+
+        self.button_chan.send((action, button));
+    }
+}
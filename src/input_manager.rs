@@ -22,38 +22,66 @@
 use std::num;
 
 use glfw;
+use key_bindings::{KeyBindings, InputAction};
 use math::Vec2;
 use timer::Timer;
 
 #[deriving(Clone, ToStr)]
 pub struct InputDatas {
     keys:           ~[(glfw::Action, glfw::Key)],
-    mouse_position: Vec2<f32>
+    mouse_buttons:  ~[(glfw::Action, glfw::MouseButton)],
+    mouse_position: Vec2<f32>,
+    // actions whose key is down
+    held:           ~[InputAction],
+    // actions whose key went down since the last frame
+    pressed:        ~[InputAction]
+}
+
+impl InputDatas {
+    pub fn is_held(&self, action: InputAction) -> bool {
+        self.held.contains(&action)
+    }
+
+    pub fn was_pressed(&self, action: InputAction) -> bool {
+        self.pressed.contains(&action)
+    }
 }
 
 pub struct InputManager {
     priv key_port:      Port<(glfw::Action, glfw::Key)>,
+    priv mouse_port:    Port<(glfw::Action, glfw::MouseButton)>,
+    priv bindings:      KeyBindings,
     priv timer:         Timer
 }
 
 
 impl InputManager {
-    pub fn new(k_port: Port<(glfw::Action, glfw::Key)>) -> InputManager {
+    pub fn new(k_port: Port<(glfw::Action, glfw::Key)>,
+        m_port: Port<(glfw::Action, glfw::MouseButton)>) -> InputManager {
         InputManager {
             key_port:       k_port,
+            mouse_port:     m_port,
+            bindings:       KeyBindings::new(),
             timer:          Timer::new()
         }
     }
 
+    pub fn get_bindings<'a>(&'a self) -> &'a KeyBindings {
+        &self.bindings
+    }
+
+    pub fn get_bindings_mut<'a>(&'a mut self) -> &'a mut KeyBindings {
+        &mut self.bindings
+    }
+
+    // the bound keys are polled, a key held down sends no event
     pub fn check_input(&mut self, 
         window: &glfw::Window,
-        keys: &mut ~[(glfw::Action, glfw::Key)]) -> () {
-
-        match window.get_key(glfw::KeyW) { glfw::Press => keys.push((glfw::Press, glfw::KeyW)), _ => {} }
-        match window.get_key(glfw::KeyD) { glfw::Press => keys.push((glfw::Press, glfw::KeyD)), _ => {} }
-        match window.get_key(glfw::KeyS) { glfw::Press => keys.push((glfw::Press, glfw::KeyS)), _ => {} }
-        match window.get_key(glfw::KeyA) { glfw::Press => keys.push((glfw::Press, glfw::KeyA)), _ => {} }
+        held: &mut ~[InputAction]) -> () {
 
+        for &(action, key) in self.bindings.get_bindings().iter() {
+            match window.get_key(key) { glfw::Press => held.push(action), _ => {} }
+        }
     }
 
     pub fn update(&mut self, window: &glfw::Window) -> InputDatas {
@@ -66,6 +94,14 @@ impl InputManager {
             }
         }
 
+        let mut buttons: ~[(glfw::Action, glfw::MouseButton)] = ~[];
+        loop {
+            match self.mouse_port.try_recv() {
+                Some((a, b))    => { buttons.push((a, b)) },
+                None            => break
+            }
+        }
+
         let mouse_pos = match window.get_cursor_pos() {
             (x, y)  => { // println!("MOUSE_X: {:f} / MOUSE_Y: {:f}", x, y);
                 if (x == 0f64 && y == 0f64) {
@@ -86,10 +122,21 @@ impl InputManager {
             };
             // self.timer.reset()
         // }
-        self.check_input(window, &mut inputs);
+        let mut held = ~[];
+        self.check_input(window, &mut held);
+        let mut pressed = ~[];
+        for &(a, k) in inputs.iter() {
+            match a {
+                glfw::Press => pressed.push_all_move(self.bindings.get_actions(k)),
+                _           => {}
+            }
+        }
         InputDatas {
             keys:           inputs,
-            mouse_position: mouse_pos
+            mouse_buttons:  buttons,
+            mouse_position: mouse_pos,
+            held:           held,
+            pressed:        pressed
         }        
     }   
 }
@@ -0,0 +1,81 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// The player is driven by actions, the keys behind them can be rebound.
+
+use glfw;
+
+#[deriving(Clone, Eq, ToStr)]
+pub enum InputAction {
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Crouch,
+    Sprint,
+    // walking, flying then noclip
+    CycleMoveMode
+}
+
+pub struct KeyBindings {
+    priv bindings: ~[(InputAction, glfw::Key)]
+}
+
+impl KeyBindings {
+    pub fn new() -> KeyBindings {
+        KeyBindings {
+            bindings: ~[
+                (MoveForward,   glfw::KeyW),
+                (MoveBack,      glfw::KeyS),
+                (MoveLeft,      glfw::KeyA),
+                (MoveRight,     glfw::KeyD),
+                (Jump,          glfw::KeySpace),
+                (Crouch,        glfw::KeyLeftShift),
+                (Sprint,        glfw::KeyLeftControl),
+                (CycleMoveMode, glfw::KeyF)
+            ]
+        }
+    }
+
+    pub fn get_key(&self, action: InputAction) -> Option<glfw::Key> {
+        self.bindings.iter().find(|&&(a, _)| a == action).map(|&(_, k)| k)
+    }
+
+    // one key per action, the old key of `action` is released
+    pub fn bind(&mut self, action: InputAction, key: glfw::Key) -> () {
+        self.bindings.retain(|&(a, _)| a != action);
+        self.bindings.push((action, key));
+    }
+
+    pub fn unbind(&mut self, action: InputAction) -> () {
+        self.bindings.retain(|&(a, _)| a != action);
+    }
+
+    // a key may drive several actions
+    pub fn get_actions(&self, key: glfw::Key) -> ~[InputAction] {
+        self.bindings.iter().filter(|&&(_, k)| k == key).map(|&(a, _)| a).collect()
+    }
+
+    pub fn get_bindings<'a>(&'a self) -> &'a [(InputAction, glfw::Key)] {
+        self.bindings.as_slice()
+    }
+}
@@ -36,6 +36,8 @@ extern mod gl;
 extern mod glfw;
 extern mod stb = "stb_image";
 
+use std::os;
+
 use game::Game;
 
 mod glfw_utils;
@@ -43,15 +45,18 @@ mod game;
 mod perf_metrics;
 mod shaders;
 mod math;
-mod cube;
+mod chunk_renderer;
 mod texture_loader;
 mod world;
 mod font;
 mod text;
 mod input_manager;
+mod key_bindings;
 mod camera;
+mod player;
 mod timer;
 mod noise;
+mod terrain;
 
 #[cfg(target_os="macos")]
 #[link(name = "glfw3")]
@@ -67,9 +72,12 @@ fn start(argc: int, argv: **u8) -> int {
 }
 
 
+// usage: rustedcraft [preset], the preset of a new world, see presets/
 fn main() {
+    let args = os::args();
+    let preset_name = if args.len() > 1 { args[1].clone() } else { ~"default" };
     do glfw::start {
-        let mut game = Game::new();
+        let mut game = Game::new(preset_name.as_slice());
         game.run();
     }
 }
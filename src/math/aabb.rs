@@ -0,0 +1,175 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// Axis aligned boxes, used to collide the player against the blocks.
+// The axis are numbered 0 for x, 1 for y and 2 for z.
+
+use math::Vec3;
+
+// two boxes closer than this are touching, it absorbs the rounding errors
+static EPSILON: f64 = 1e-7;
+
+#[deriving(Clone, Eq, ToStr)]
+pub struct Aabb {
+    min: Vec3<f64>,
+    max: Vec3<f64>
+}
+
+impl Aabb {
+    pub fn new(min: Vec3<f64>, max: Vec3<f64>) -> Aabb {
+        Aabb {
+            min: min,
+            max: max
+        }
+    }
+
+    // the box of the block at (x, y, z)
+    pub fn block(x: i32, y: i32, z: i32) -> Aabb {
+        Aabb::new(Vec3::new(x as f64, y as f64, z as f64),
+            Vec3::new((x + 1) as f64, (y + 1) as f64, (z + 1) as f64))
+    }
+
+    pub fn offset(&self, dx: f64, dy: f64, dz: f64) -> Aabb {
+        let d = Vec3::new(dx, dy, dz);
+        Aabb::new(self.min.add_vec(&d), self.max.add_vec(&d))
+    }
+
+    pub fn offset_axis(&self, axis: uint, d: f64) -> Aabb {
+        match axis {
+            0   => self.offset(d, 0f64, 0f64),
+            1   => self.offset(0f64, d, 0f64),
+            _   => self.offset(0f64, 0f64, d)
+        }
+    }
+
+    // grown toward the movement, covers every position along it
+    pub fn expand(&self, dx: f64, dy: f64, dz: f64) -> Aabb {
+        let mut b = self.clone();
+        if dx < 0f64 { b.min.x += dx } else { b.max.x += dx }
+        if dy < 0f64 { b.min.y += dy } else { b.max.y += dy }
+        if dz < 0f64 { b.min.z += dz } else { b.max.z += dz }
+        b
+    }
+
+    // touching boxes do not intersect
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        range(0u, 3).all(|axis| self.overlaps_on(other, axis))
+    }
+
+    // how far `moving` can go along `axis`, at most `d`, before it hits
+    // this box. a box already inside this one is not stopped
+    pub fn clip(&self, moving: &Aabb, axis: uint, d: f64) -> f64 {
+        for other in range(0u, 3) {
+            if other != axis && !self.overlaps_on(moving, other) {
+                return d;
+            }
+        }
+        if d > 0f64 && component(&self.min, axis) >= component(&moving.max, axis) - EPSILON {
+            let gap = component(&self.min, axis) - component(&moving.max, axis);
+            if gap < 0f64 { 0f64 } else if gap < d { gap } else { d }
+        } else if d < 0f64 && component(&self.max, axis) <= component(&moving.min, axis) + EPSILON {
+            let gap = component(&self.max, axis) - component(&moving.min, axis);
+            if gap > 0f64 { 0f64 } else if gap > d { gap } else { d }
+        } else {
+            d
+        }
+    }
+
+    fn overlaps_on(&self, other: &Aabb, axis: uint) -> bool {
+        component(&self.min, axis) < component(&other.max, axis) - EPSILON
+            && component(&self.max, axis) > component(&other.min, axis) + EPSILON
+    }
+}
+
+pub fn component(v: &Vec3<f64>, axis: uint) -> f64 {
+    match axis {
+        0   => v.x,
+        1   => v.y,
+        _   => v.z
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use math::Vec3;
+    use super::Aabb;
+
+    // a unit box with its min corner at (x, y, z)
+    fn unit(x: f64, y: f64, z: f64) -> Aabb {
+        Aabb::new(Vec3::new(x, y, z), Vec3::new(x + 1f64, y + 1f64, z + 1f64))
+    }
+
+    #[test]
+    fn clips_on_each_axis_in_both_directions() {
+        let obstacle = unit(0f64, 0f64, 0f64);
+        for axis in range(0u, 3) {
+            let (before, after) = (unit(0f64, 0f64, 0f64).offset_axis(axis, -3f64), unit(0f64, 0f64, 0f64).offset_axis(axis, 3f64));
+            // 2 blocks of gap on each side
+            assert_eq!(obstacle.clip(&before, axis, 5f64), 2f64);
+            assert_eq!(obstacle.clip(&after, axis, -5f64), -2f64);
+            // short of the obstacle, the move is kept
+            assert_eq!(obstacle.clip(&before, axis, 1f64), 1f64);
+            assert_eq!(obstacle.clip(&after, axis, -1f64), -1f64);
+            // moving away is never stopped
+            assert_eq!(obstacle.clip(&before, axis, -5f64), -5f64);
+            assert_eq!(obstacle.clip(&after, axis, 5f64), 5f64);
+        }
+    }
+
+    #[test]
+    fn does_not_clip_boxes_passing_beside() {
+        let obstacle = unit(0f64, 0f64, 0f64);
+        // one block higher, moving along x
+        assert_eq!(obstacle.clip(&unit(-3f64, 1f64, 0f64), 0, 5f64), 5f64);
+        // overlapping by half a block on y, it is stopped
+        assert_eq!(obstacle.clip(&unit(-3f64, 0.5f64, 0f64), 0, 5f64), 2f64);
+    }
+
+    #[test]
+    fn touching_boxes_do_not_intersect() {
+        let a = unit(0f64, 0f64, 0f64);
+        assert!(!a.intersects(&unit(1f64, 0f64, 0f64)));
+        assert!(!a.intersects(&unit(0f64, -1f64, 0f64)));
+        assert!(!a.intersects(&unit(0f64, 0f64, 1f64)));
+        assert!(a.intersects(&unit(0.5f64, 0.5f64, 0.5f64)));
+        // within the epsilon, still touching
+        assert!(!a.intersects(&unit(1f64 - 1e-9, 0f64, 0f64)));
+    }
+
+    #[test]
+    fn touching_box_cannot_move_in() {
+        let obstacle = unit(0f64, 0f64, 0f64);
+        assert_eq!(obstacle.clip(&unit(-1f64, 0f64, 0f64), 0, 0.5f64), 0f64);
+        assert_eq!(obstacle.clip(&unit(0f64, 1f64, 0f64), 1, -0.5f64), 0f64);
+        // a rounding error inside the obstacle is pushed back
+        assert_eq!(obstacle.clip(&unit(-1f64 + 1e-9, 0f64, 0f64), 0, 0.5f64), 0f64);
+    }
+
+    #[test]
+    fn overlapping_box_is_not_stopped() {
+        let obstacle = unit(0f64, 0f64, 0f64);
+        let inside = unit(0.5f64, 0.5f64, 0.5f64);
+        for axis in range(0u, 3) {
+            assert_eq!(obstacle.clip(&inside, axis, 2f64), 2f64);
+            assert_eq!(obstacle.clip(&inside, axis, -2f64), -2f64);
+        }
+    }
+}
@@ -19,8 +19,12 @@
 // IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
 // CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
+pub use math::aabb::Aabb;
 pub use math::mat::Mat4;
 pub use math::vec::{Vec3, Vec4, Vec2};
+pub use math::prng::Prng;
 
+pub mod aabb;
 pub mod mat;
+pub mod prng;
 pub mod vec;
@@ -0,0 +1,82 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// SplitMix64, small and gives the same sequence on every platform so a
+// seed always generates the same world.
+// http://xorshift.di.unimi.it/splitmix64.c
+
+pub struct Prng {
+    priv state: u64
+}
+
+impl Prng {
+    pub fn new(seed: u64) -> Prng {
+        Prng {
+            state: seed
+        }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state += 0x9E3779B97F4A7C15;
+        mix(self.state)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    // [0, 1)
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / 9007199254740992f64
+    }
+
+    pub fn next_f32(&mut self) -> f32 {
+        self.next_f64() as f32
+    }
+
+    // [low, high)
+    pub fn gen_range(&mut self, low: i32, high: i32) -> i32 {
+        if high <= low {
+            low
+        } else {
+            low + (self.next_u64() % (high - low) as u64) as i32
+        }
+    }
+
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+}
+
+fn mix(value: u64) -> u64 {
+    let mut z = value;
+    z = (z ^ (z >> 30)) * 0xBF58476D1CE4E5B9;
+    z = (z ^ (z >> 27)) * 0x94D049BB133111EB;
+    z ^ (z >> 31)
+}
+
+// derive an independent seed from the world seed, a position and a salt
+// so that each generation pass of each chunk gets its own sequence
+pub fn hash_seed(seed: u64, x: i32, z: i32, salt: u64) -> u64 {
+    let mut h = mix(seed ^ salt);
+    h = mix(h ^ (x as u32 as u64));
+    mix(h ^ ((z as u32 as u64) << 32))
+}
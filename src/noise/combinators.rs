@@ -0,0 +1,326 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use noise::source::NoiseSource;
+
+pub struct Constant {
+    priv value: f64
+}
+
+impl Constant {
+    pub fn new(value: f64) -> Constant {
+        Constant { value: value }
+    }
+}
+
+impl NoiseSource for Constant {
+    fn get(&self, _: f64, _: f64, _: f64) -> f64 {
+        self.value
+    }
+}
+
+// scale the input coordinates, ie. the frequency of the source
+pub struct ScalePoint {
+    priv source:    ~NoiseSource,
+    priv x:         f64,
+    priv y:         f64,
+    priv z:         f64
+}
+
+impl ScalePoint {
+    pub fn new(source: ~NoiseSource, x: f64, y: f64, z: f64) -> ScalePoint {
+        ScalePoint { source: source, x: x, y: y, z: z }
+    }
+}
+
+impl NoiseSource for ScalePoint {
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.source.get(x * self.x, y * self.y, z * self.z)
+    }
+}
+
+// fractal Brownian motion, octaves of the source summed with a frequency
+// multiplied by lacunarity and an amplitude multiplied by gain each octave
+pub struct Fbm {
+    priv source:        ~NoiseSource,
+    priv octaves:       uint,
+    priv lacunarity:    f64,
+    priv gain:          f64
+}
+
+impl Fbm {
+    pub fn new(source: ~NoiseSource, octaves: uint, lacunarity: f64, gain: f64) -> Fbm {
+        Fbm { source: source, octaves: octaves, lacunarity: lacunarity, gain: gain }
+    }
+}
+
+impl NoiseSource for Fbm {
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        fractal(&self.source, self.octaves, self.lacunarity, self.gain, x, y, z, |n| n)
+    }
+}
+
+// octaves of |noise| remapped to [-1, 1], puffy cloud like shapes
+pub struct Billow {
+    priv source:        ~NoiseSource,
+    priv octaves:       uint,
+    priv lacunarity:    f64,
+    priv gain:          f64
+}
+
+impl Billow {
+    pub fn new(source: ~NoiseSource, octaves: uint, lacunarity: f64, gain: f64) -> Billow {
+        Billow { source: source, octaves: octaves, lacunarity: lacunarity, gain: gain }
+    }
+}
+
+impl NoiseSource for Billow {
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        fractal(&self.source, self.octaves, self.lacunarity, self.gain, x, y, z, |n| n.abs() * 2f64 - 1f64)
+    }
+}
+
+// Perlin's turbulence, octaves of |noise|, in [0, 1]
+pub struct Turbulence {
+    priv source:        ~NoiseSource,
+    priv octaves:       uint,
+    priv lacunarity:    f64,
+    priv gain:          f64
+}
+
+impl Turbulence {
+    pub fn new(source: ~NoiseSource, octaves: uint, lacunarity: f64, gain: f64) -> Turbulence {
+        Turbulence { source: source, octaves: octaves, lacunarity: lacunarity, gain: gain }
+    }
+}
+
+impl NoiseSource for Turbulence {
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        fractal(&self.source, self.octaves, self.lacunarity, self.gain, x, y, z, |n| n.abs())
+    }
+}
+
+// Musgrave's ridged multifractal, sharp crests for mountain ranges. Each
+// octave is weighted by the previous one so the valleys stay smooth.
+pub struct Ridged {
+    priv source:        ~NoiseSource,
+    priv octaves:       uint,
+    priv lacunarity:    f64,
+    priv gain:          f64,
+    priv offset:        f64
+}
+
+impl Ridged {
+    pub fn new(source: ~NoiseSource, octaves: uint, lacunarity: f64, gain: f64, offset: f64) -> Ridged {
+        Ridged { source: source, octaves: octaves, lacunarity: lacunarity, gain: gain, offset: offset }
+    }
+}
+
+impl NoiseSource for Ridged {
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        let mut frequency = 1f64;
+        let mut amplitude = 1f64;
+        let mut weight = 1f64;
+        let mut sum = 0f64;
+        let mut max = 0f64;
+        for _ in range(0, self.octaves) {
+            let mut signal = self.offset - self.source.get(x * frequency, y * frequency, z * frequency).abs();
+            signal = signal * signal * weight;
+            weight = clamp(signal * 2f64, 0f64, 1f64);
+            sum += signal * amplitude;
+            max += self.offset * self.offset * amplitude;
+            frequency *= self.lacunarity;
+            amplitude *= self.gain;
+        }
+        if max == 0f64 { 0f64 } else { sum / max * 2f64 - 1f64 }
+    }
+}
+
+// offset the input coordinates by three other sources
+pub struct DomainWarp {
+    priv source:    ~NoiseSource,
+    priv warp_x:    ~NoiseSource,
+    priv warp_y:    ~NoiseSource,
+    priv warp_z:    ~NoiseSource,
+    priv strength:  f64
+}
+
+impl DomainWarp {
+    pub fn new(source: ~NoiseSource, 
+        warp_x: ~NoiseSource, 
+        warp_y: ~NoiseSource, 
+        warp_z: ~NoiseSource, 
+        strength: f64) -> DomainWarp {
+
+        DomainWarp { source: source, warp_x: warp_x, warp_y: warp_y, warp_z: warp_z, strength: strength }
+    }
+}
+
+impl NoiseSource for DomainWarp {
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.source.get(x + self.warp_x.get(x, y, z) * self.strength,
+            y + self.warp_y.get(x, y, z) * self.strength,
+            z + self.warp_z.get(x, y, z) * self.strength)
+    }
+}
+
+// source * scale + bias
+pub struct ScaleBias {
+    priv source:    ~NoiseSource,
+    priv scale:     f64,
+    priv bias:      f64
+}
+
+impl ScaleBias {
+    pub fn new(source: ~NoiseSource, scale: f64, bias: f64) -> ScaleBias {
+        ScaleBias { source: source, scale: scale, bias: bias }
+    }
+}
+
+impl NoiseSource for ScaleBias {
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.source.get(x, y, z) * self.scale + self.bias
+    }
+}
+
+pub struct Clamp {
+    priv source:    ~NoiseSource,
+    priv low:       f64,
+    priv high:      f64
+}
+
+impl Clamp {
+    pub fn new(source: ~NoiseSource, low: f64, high: f64) -> Clamp {
+        Clamp { source: source, low: low, high: high }
+    }
+}
+
+impl NoiseSource for Clamp {
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        clamp(self.source.get(x, y, z), self.low, self.high)
+    }
+}
+
+pub struct Add {
+    priv a: ~NoiseSource,
+    priv b: ~NoiseSource
+}
+
+impl Add {
+    pub fn new(a: ~NoiseSource, b: ~NoiseSource) -> Add {
+        Add { a: a, b: b }
+    }
+}
+
+impl NoiseSource for Add {
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        self.a.get(x, y, z) + self.b.get(x, y, z)
+    }
+}
+
+// low where control is under threshold, high above, with a smooth
+// transition of falloff on each side of the threshold
+pub struct Select {
+    priv control:   ~NoiseSource,
+    priv low:       ~NoiseSource,
+    priv high:      ~NoiseSource,
+    priv threshold: f64,
+    priv falloff:   f64
+}
+
+impl Select {
+    pub fn new(control: ~NoiseSource, 
+        low: ~NoiseSource, 
+        high: ~NoiseSource, 
+        threshold: f64, 
+        falloff: f64) -> Select {
+
+        Select { control: control, low: low, high: high, threshold: threshold, falloff: falloff }
+    }
+}
+
+impl NoiseSource for Select {
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        let c = self.control.get(x, y, z);
+        if c < self.threshold - self.falloff {
+            self.low.get(x, y, z)
+        } else if c >= self.threshold + self.falloff {
+            self.high.get(x, y, z)
+        } else {
+            let t = smoothstep((c - (self.threshold - self.falloff)) / (2f64 * self.falloff));
+            lerp(t, self.low.get(x, y, z), self.high.get(x, y, z))
+        }
+    }
+}
+
+// a where control is -1, b where control is 1
+pub struct Blend {
+    priv a:         ~NoiseSource,
+    priv b:         ~NoiseSource,
+    priv control:   ~NoiseSource
+}
+
+impl Blend {
+    pub fn new(a: ~NoiseSource, b: ~NoiseSource, control: ~NoiseSource) -> Blend {
+        Blend { a: a, b: b, control: control }
+    }
+}
+
+impl NoiseSource for Blend {
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        let t = clamp((self.control.get(x, y, z) + 1f64) / 2f64, 0f64, 1f64);
+        lerp(t, self.a.get(x, y, z), self.b.get(x, y, z))
+    }
+}
+
+// octaves of f(source) normalized by the sum of the amplitudes
+fn fractal(source: &~NoiseSource, 
+    octaves: uint, 
+    lacunarity: f64, 
+    gain: f64, 
+    x: f64, y: f64, z: f64, 
+    f: |f64| -> f64) -> f64 {
+
+    let mut frequency = 1f64;
+    let mut amplitude = 1f64;
+    let mut sum = 0f64;
+    let mut max = 0f64;
+    for _ in range(0, octaves) {
+        sum += amplitude * f(source.get(x * frequency, y * frequency, z * frequency));
+        max += amplitude;
+        frequency *= lacunarity;
+        amplitude *= gain;
+    }
+    if max == 0f64 { 0f64 } else { sum / max }
+}
+
+pub fn clamp(v: f64, low: f64, high: f64) -> f64 {
+    if v < low { low } else if v > high { high } else { v }
+}
+
+pub fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+pub fn smoothstep(t: f64) -> f64 {
+    let t = clamp(t, 0f64, 1f64);
+    t * t * (3f64 - 2f64 * t)
+}
@@ -20,6 +20,12 @@
 // CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
 
 pub use noise::perlin_noise::PerlinNoise;
+pub use noise::simplex::SimplexNoise;
+pub use noise::source::NoiseSource;
+pub use noise::worley::WorleyNoise;
 
+pub mod combinators;
 pub mod perlin_noise;
-// pub mod simplex;
\ No newline at end of file
+pub mod simplex;
+pub mod source;
+pub mod worley;
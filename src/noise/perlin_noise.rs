@@ -25,6 +25,8 @@
 use std::num::one;
 use std::num;
 
+use math::Prng;
+
 pub struct PerlinNoise<T> {
     priv permutations: ~[i32]
 }
@@ -38,6 +40,13 @@ impl<T: Float + Real + Clone + Round + NumCast> PerlinNoise<T> {
 
     }
 
+    // Ken Perlin's table shuffled with the seed
+    pub fn with_seed(seed: u64) -> PerlinNoise<T> {
+        PerlinNoise {
+            permutations: shuffled_permutations(seed)
+        }
+    }
+
     pub fn noise(&self, x: T, y: T, z: T) -> T {
         let X = num::cast::<T, i32>(x.floor()).unwrap() & 255;
         let Y = num::cast::<T, i32>(y.floor()).unwrap() & 255;
@@ -69,6 +78,21 @@ impl<T: Float + Real + Clone + Round + NumCast> PerlinNoise<T> {
     }
 }
 
+// 256 values shuffled (Fisher-Yates) then repeated, like PERMUTATIONS
+pub fn shuffled_permutations(seed: u64) -> ~[i32] {
+    let mut rng = Prng::new(seed);
+    let mut p = PERMUTATIONS.slice(0, 256).to_owned();
+    let mut i = 255u;
+    while i > 0 {
+        let j = (rng.next_u64() % (i as u64 + 1)) as uint;
+        p.swap(i, j);
+        i -= 1;
+    }
+    let repeat = p.clone();
+    p.push_all_move(repeat);
+    p
+}
+
 fn fade<T: Float + Real + Clone + Round + NumCast>(t: T) -> T 
 { t * t * t * (t * (t * num::cast(6).unwrap() - num::cast(15).unwrap()) + num::cast(10).unwrap()) }
 
@@ -82,7 +106,7 @@ fn grad<T: Float + Real + Clone + Round + NumCast>(hash: i32, x: T, y: T, z: T)
     (if (h & 1) == 0 { u } else { -u }) + (if (h & 2) == 0 { v } else { -v })
 }
 
-static  PERMUTATIONS: [i32, ..512]= [151,160,137,91,90,15,
+pub static PERMUTATIONS: [i32, ..512] = [151,160,137,91,90,15,
     131,13,201,95,96,53,194,233,7,225,140,36,103,30,69,142,8,99,37,240,21,10,23,
     190, 6,148,247,120,234,75,0,26,197,62,94,252,219,203,117,35,11,32,57,177,33,
     88,237,149,56,87,174,20,125,136,171,168, 68,175,74,165,71,134,139,48,27,166,
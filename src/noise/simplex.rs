@@ -0,0 +1,253 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// SIMPLEX NOISE, BASED ON THE JAVA IMPLEMENTATION BY STEFAN GUSTAVSON
+// http://webstaff.itn.liu.se/~stegu/simplexnoise/SimplexNoise.java
+
+use std::num;
+
+use noise::perlin_noise::{PERMUTATIONS, shuffled_permutations};
+
+// skewing and unskewing factors: (sqrt(n + 1) - 1) / n and (n + 1 - sqrt(n + 1)) / (n * (n + 1))
+static F2: f64 = 0.36602540378443865;
+static G2: f64 = 0.21132486540518713;
+static F3: f64 = 0.33333333333333333;
+static G3: f64 = 0.16666666666666667;
+static F4: f64 = 0.30901699437494745;
+static G4: f64 = 0.13819660112501052;
+
+pub struct SimplexNoise<T> {
+    priv permutations: ~[i32]
+}
+
+impl<T: Float + Real + Clone + Round + NumCast> SimplexNoise<T> {
+    pub fn new() -> SimplexNoise<T> {
+        SimplexNoise {
+            permutations: PERMUTATIONS.to_owned()
+        }
+    }
+
+    pub fn with_seed(seed: u64) -> SimplexNoise<T> {
+        SimplexNoise {
+            permutations: shuffled_permutations(seed)
+        }
+    }
+
+    pub fn noise2(&self, x: T, y: T) -> T {
+        to_t(self.simplex2(from_t(x), from_t(y)))
+    }
+
+    pub fn noise3(&self, x: T, y: T, z: T) -> T {
+        to_t(self.simplex3(from_t(x), from_t(y), from_t(z)))
+    }
+
+    pub fn noise4(&self, x: T, y: T, z: T, w: T) -> T {
+        to_t(self.simplex4(from_t(x), from_t(y), from_t(z), from_t(w)))
+    }
+
+    fn perm(&self, i: i32) -> i32 {
+        self.permutations[i as uint]
+    }
+
+    fn simplex2(&self, xin: f64, yin: f64) -> f64 {
+        // skew the input space to find the simplex cell
+        let s = (xin + yin) * F2;
+        let i = (xin + s).floor();
+        let j = (yin + s).floor();
+        let t = (i + j) * G2;
+        let x0 = xin - (i - t);
+        let y0 = yin - (j - t);
+
+        // upper or lower triangle
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+
+        let x1 = x0 - i1 as f64 + G2;
+        let y1 = y0 - j1 as f64 + G2;
+        let x2 = x0 - 1f64 + 2f64 * G2;
+        let y2 = y0 - 1f64 + 2f64 * G2;
+
+        let ii = (i as i32) & 255;
+        let jj = (j as i32) & 255;
+        let gi0 = self.perm(ii + self.perm(jj)) % 12;
+        let gi1 = self.perm(ii + i1 + self.perm(jj + j1)) % 12;
+        let gi2 = self.perm(ii + 1 + self.perm(jj + 1)) % 12;
+
+        let n0 = corner(0.5 - x0 * x0 - y0 * y0, dot3(gi0, x0, y0, 0f64));
+        let n1 = corner(0.5 - x1 * x1 - y1 * y1, dot3(gi1, x1, y1, 0f64));
+        let n2 = corner(0.5 - x2 * x2 - y2 * y2, dot3(gi2, x2, y2, 0f64));
+
+        // scaled to [-1, 1]
+        70f64 * (n0 + n1 + n2)
+    }
+
+    fn simplex3(&self, xin: f64, yin: f64, zin: f64) -> f64 {
+        let s = (xin + yin + zin) * F3;
+        let i = (xin + s).floor();
+        let j = (yin + s).floor();
+        let k = (zin + s).floor();
+        let t = (i + j + k) * G3;
+        let x0 = xin - (i - t);
+        let y0 = yin - (j - t);
+        let z0 = zin - (k - t);
+
+        // offsets of the second and third corners of the simplex
+        let (i1, j1, k1, i2, j2, k2) = if x0 >= y0 {
+            if y0 >= z0 { (1, 0, 0, 1, 1, 0) }
+            else if x0 >= z0 { (1, 0, 0, 1, 0, 1) }
+            else { (0, 0, 1, 1, 0, 1) }
+        } else {
+            if y0 < z0 { (0, 0, 1, 0, 1, 1) }
+            else if x0 < z0 { (0, 1, 0, 0, 1, 1) }
+            else { (0, 1, 0, 1, 1, 0) }
+        };
+
+        let x1 = x0 - i1 as f64 + G3;
+        let y1 = y0 - j1 as f64 + G3;
+        let z1 = z0 - k1 as f64 + G3;
+        let x2 = x0 - i2 as f64 + 2f64 * G3;
+        let y2 = y0 - j2 as f64 + 2f64 * G3;
+        let z2 = z0 - k2 as f64 + 2f64 * G3;
+        let x3 = x0 - 1f64 + 3f64 * G3;
+        let y3 = y0 - 1f64 + 3f64 * G3;
+        let z3 = z0 - 1f64 + 3f64 * G3;
+
+        let ii = (i as i32) & 255;
+        let jj = (j as i32) & 255;
+        let kk = (k as i32) & 255;
+        let gi0 = self.perm(ii + self.perm(jj + self.perm(kk))) % 12;
+        let gi1 = self.perm(ii + i1 + self.perm(jj + j1 + self.perm(kk + k1))) % 12;
+        let gi2 = self.perm(ii + i2 + self.perm(jj + j2 + self.perm(kk + k2))) % 12;
+        let gi3 = self.perm(ii + 1 + self.perm(jj + 1 + self.perm(kk + 1))) % 12;
+
+        let n0 = corner(0.6 - x0 * x0 - y0 * y0 - z0 * z0, dot3(gi0, x0, y0, z0));
+        let n1 = corner(0.6 - x1 * x1 - y1 * y1 - z1 * z1, dot3(gi1, x1, y1, z1));
+        let n2 = corner(0.6 - x2 * x2 - y2 * y2 - z2 * z2, dot3(gi2, x2, y2, z2));
+        let n3 = corner(0.6 - x3 * x3 - y3 * y3 - z3 * z3, dot3(gi3, x3, y3, z3));
+
+        32f64 * (n0 + n1 + n2 + n3)
+    }
+
+    fn simplex4(&self, xin: f64, yin: f64, zin: f64, win: f64) -> f64 {
+        let s = (xin + yin + zin + win) * F4;
+        let i = (xin + s).floor();
+        let j = (yin + s).floor();
+        let k = (zin + s).floor();
+        let l = (win + s).floor();
+        let t = (i + j + k + l) * G4;
+        let x0 = xin - (i - t);
+        let y0 = yin - (j - t);
+        let z0 = zin - (k - t);
+        let w0 = win - (l - t);
+
+        // rank the coordinates to find in which of the 24 simplices we are
+        let mut rank = [0i32, ..4];
+        if x0 > y0 { rank[0] += 1; } else { rank[1] += 1; }
+        if x0 > z0 { rank[0] += 1; } else { rank[2] += 1; }
+        if x0 > w0 { rank[0] += 1; } else { rank[3] += 1; }
+        if y0 > z0 { rank[1] += 1; } else { rank[2] += 1; }
+        if y0 > w0 { rank[1] += 1; } else { rank[3] += 1; }
+        if z0 > w0 { rank[2] += 1; } else { rank[3] += 1; }
+        let step = |r: i32| -> (i32, i32, i32, i32) {
+            ((rank[0] >= r) as i32, (rank[1] >= r) as i32, (rank[2] >= r) as i32, (rank[3] >= r) as i32)
+        };
+        let (i1, j1, k1, l1) = step(3);
+        let (i2, j2, k2, l2) = step(2);
+        let (i3, j3, k3, l3) = step(1);
+
+        let x1 = x0 - i1 as f64 + G4;
+        let y1 = y0 - j1 as f64 + G4;
+        let z1 = z0 - k1 as f64 + G4;
+        let w1 = w0 - l1 as f64 + G4;
+        let x2 = x0 - i2 as f64 + 2f64 * G4;
+        let y2 = y0 - j2 as f64 + 2f64 * G4;
+        let z2 = z0 - k2 as f64 + 2f64 * G4;
+        let w2 = w0 - l2 as f64 + 2f64 * G4;
+        let x3 = x0 - i3 as f64 + 3f64 * G4;
+        let y3 = y0 - j3 as f64 + 3f64 * G4;
+        let z3 = z0 - k3 as f64 + 3f64 * G4;
+        let w3 = w0 - l3 as f64 + 3f64 * G4;
+        let x4 = x0 - 1f64 + 4f64 * G4;
+        let y4 = y0 - 1f64 + 4f64 * G4;
+        let z4 = z0 - 1f64 + 4f64 * G4;
+        let w4 = w0 - 1f64 + 4f64 * G4;
+
+        let ii = (i as i32) & 255;
+        let jj = (j as i32) & 255;
+        let kk = (k as i32) & 255;
+        let ll = (l as i32) & 255;
+        let gi0 = self.perm(ii + self.perm(jj + self.perm(kk + self.perm(ll)))) % 32;
+        let gi1 = self.perm(ii + i1 + self.perm(jj + j1 + self.perm(kk + k1 + self.perm(ll + l1)))) % 32;
+        let gi2 = self.perm(ii + i2 + self.perm(jj + j2 + self.perm(kk + k2 + self.perm(ll + l2)))) % 32;
+        let gi3 = self.perm(ii + i3 + self.perm(jj + j3 + self.perm(kk + k3 + self.perm(ll + l3)))) % 32;
+        let gi4 = self.perm(ii + 1 + self.perm(jj + 1 + self.perm(kk + 1 + self.perm(ll + 1)))) % 32;
+
+        let n0 = corner(0.6 - x0 * x0 - y0 * y0 - z0 * z0 - w0 * w0, dot4(gi0, x0, y0, z0, w0));
+        let n1 = corner(0.6 - x1 * x1 - y1 * y1 - z1 * z1 - w1 * w1, dot4(gi1, x1, y1, z1, w1));
+        let n2 = corner(0.6 - x2 * x2 - y2 * y2 - z2 * z2 - w2 * w2, dot4(gi2, x2, y2, z2, w2));
+        let n3 = corner(0.6 - x3 * x3 - y3 * y3 - z3 * z3 - w3 * w3, dot4(gi3, x3, y3, z3, w3));
+        let n4 = corner(0.6 - x4 * x4 - y4 * y4 - z4 * z4 - w4 * w4, dot4(gi4, x4, y4, z4, w4));
+
+        27f64 * (n0 + n1 + n2 + n3 + n4)
+    }
+}
+
+// contribution of a simplex corner
+fn corner(t: f64, gradient: f64) -> f64 {
+    if t < 0f64 {
+        0f64
+    } else {
+        let t2 = t * t;
+        t2 * t2 * gradient
+    }
+}
+
+fn dot3(gi: i32, x: f64, y: f64, z: f64) -> f64 {
+    let g = GRAD3[gi as uint];
+    g[0] * x + g[1] * y + g[2] * z
+}
+
+fn dot4(gi: i32, x: f64, y: f64, z: f64, w: f64) -> f64 {
+    let g = GRAD4[gi as uint];
+    g[0] * x + g[1] * y + g[2] * z + g[3] * w
+}
+
+fn from_t<T: NumCast>(v: T) -> f64 {
+    num::cast::<T, f64>(v).unwrap()
+}
+
+fn to_t<T: NumCast>(v: f64) -> T {
+    num::cast::<f64, T>(v).unwrap()
+}
+
+static GRAD3: [[f64, ..3], ..12] = [
+    [1., 1., 0.], [-1., 1., 0.], [1., -1., 0.], [-1., -1., 0.],
+    [1., 0., 1.], [-1., 0., 1.], [1., 0., -1.], [-1., 0., -1.],
+    [0., 1., 1.], [0., -1., 1.], [0., 1., -1.], [0., -1., -1.]];
+
+static GRAD4: [[f64, ..4], ..32] = [
+    [0., 1., 1., 1.], [0., 1., 1., -1.], [0., 1., -1., 1.], [0., 1., -1., -1.],
+    [0., -1., 1., 1.], [0., -1., 1., -1.], [0., -1., -1., 1.], [0., -1., -1., -1.],
+    [1., 0., 1., 1.], [1., 0., 1., -1.], [1., 0., -1., 1.], [1., 0., -1., -1.],
+    [-1., 0., 1., 1.], [-1., 0., 1., -1.], [-1., 0., -1., 1.], [-1., 0., -1., -1.],
+    [1., 1., 0., 1.], [1., 1., 0., -1.], [1., -1., 0., 1.], [1., -1., 0., -1.],
+    [-1., 1., 0., 1.], [-1., 1., 0., -1.], [-1., -1., 0., 1.], [-1., -1., 0., -1.],
+    [1., 1., 1., 0.], [1., 1., -1., 0.], [1., -1., 1., 0.], [1., -1., -1., 0.],
+    [-1., 1., 1., 0.], [-1., 1., -1., 0.], [-1., -1., 1., 0.], [-1., -1., -1., 0.]];
@@ -0,0 +1,47 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::num;
+
+use noise::perlin_noise::PerlinNoise;
+use noise::simplex::SimplexNoise;
+
+// Anything giving a value, roughly in [-1, 1], for a point in space. The
+// combinators wrap sources so that noise graphs can be built by composition.
+pub trait NoiseSource {
+    fn get(&self, x: f64, y: f64, z: f64) -> f64;
+}
+
+impl<T: Float + Real + Clone + Round + NumCast> NoiseSource for PerlinNoise<T> {
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        num::cast::<T, f64>(self.noise(cast(x), cast(y), cast(z))).unwrap()
+    }
+}
+
+impl<T: Float + Real + Clone + Round + NumCast> NoiseSource for SimplexNoise<T> {
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        num::cast::<T, f64>(self.noise3(cast(x), cast(y), cast(z))).unwrap()
+    }
+}
+
+fn cast<T: NumCast>(v: f64) -> T {
+    num::cast::<f64, T>(v).unwrap()
+}
@@ -0,0 +1,142 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// Worley (cellular) noise, one feature point per unit cell
+// http://www.rhythmiccanvas.com/research/papers/worley.pdf
+
+use std::num::sqrt;
+
+use math::Prng;
+use math::prng::hash_seed;
+use noise::source::NoiseSource;
+
+#[deriving(Clone, Eq, ToStr)]
+pub enum DistanceMetric {
+    Euclidean,
+    Manhattan,
+    Chebyshev
+}
+
+#[deriving(Clone, Eq, ToStr)]
+pub enum WorleyReturn {
+    // distance to the closest feature point
+    F1,
+    // distance to the second closest feature point
+    F2,
+    // borders between the cells
+    F2MinusF1,
+    // a constant value per cell, in [-1, 1]
+    CellId
+}
+
+pub struct WorleyNoise {
+    priv seed:      u64,
+    priv metric:    DistanceMetric,
+    priv mode:      WorleyReturn
+}
+
+impl WorleyNoise {
+    pub fn new(seed: u64) -> WorleyNoise {
+        WorleyNoise::with_settings(seed, Euclidean, F1)
+    }
+
+    pub fn with_settings(seed: u64, metric: DistanceMetric, mode: WorleyReturn) -> WorleyNoise {
+        WorleyNoise {
+            seed:   seed,
+            metric: metric,
+            mode:   mode
+        }
+    }
+
+    fn feature_point(&self, cx: i32, cy: i32, cz: i32) -> (f64, f64, f64) {
+        let mut rng = Prng::new(hash_seed(self.seed, cx, cz, cy as u32 as u64));
+        (cx as f64 + rng.next_f64(), cy as f64 + rng.next_f64(), cz as f64 + rng.next_f64())
+    }
+
+    fn distance(&self, dx: f64, dy: f64, dz: f64) -> f64 {
+        match self.metric {
+            Euclidean   => sqrt(dx * dx + dy * dy + dz * dz),
+            Manhattan   => dx.abs() + dy.abs() + dz.abs(),
+            Chebyshev   => {
+                let m = if dx.abs() > dy.abs() { dx.abs() } else { dy.abs() };
+                if m > dz.abs() { m } else { dz.abs() }
+            }
+        }
+    }
+}
+
+impl NoiseSource for WorleyNoise {
+    fn get(&self, x: f64, y: f64, z: f64) -> f64 {
+        let (cx, cy, cz) = (x.floor() as i32, y.floor() as i32, z.floor() as i32);
+        let mut f1: f64 = Float::infinity();
+        let mut f2: f64 = Float::infinity();
+        let mut closest = (cx, cy, cz);
+
+        // shells of cells around the point's cell, the cells of shell r are
+        // at least r - 1 away, so the search stops once that passes f2. a
+        // cell which can't be closer than f2 is skipped before its point
+        let mut r = 0i32;
+        while ((r - 1) as f64) <= f2 {
+            for dx in range(-r, r + 1) {
+                for dy in range(-r, r + 1) {
+                    for dz in range(-r, r + 1) {
+                        if dx.abs() != r && dy.abs() != r && dz.abs() != r {
+                            continue;
+                        }
+                        let (ix, iy, iz) = (cx + dx, cy + dy, cz + dz);
+                        let nearest = self.distance(gap(x, ix), gap(y, iy), gap(z, iz));
+                        if nearest >= f2 {
+                            continue;
+                        }
+                        let (px, py, pz) = self.feature_point(ix, iy, iz);
+                        let d = self.distance(px - x, py - y, pz - z);
+                        if d < f1 {
+                            f2 = f1;
+                            f1 = d;
+                            closest = (ix, iy, iz);
+                        } else if d < f2 {
+                            f2 = d;
+                        }
+                    }
+                }
+            }
+            r += 1;
+        }
+
+        // distances are mostly in [0, 1], remapped to [-1, 1]
+        match self.mode {
+            F1          => f1 * 2f64 - 1f64,
+            F2          => f2 * 2f64 - 1f64,
+            F2MinusF1   => (f2 - f1) * 2f64 - 1f64,
+            CellId      => {
+                let (x, y, z) = closest;
+                let mut rng = Prng::new(hash_seed(self.seed ^ 0x5bd1e995, x, z, y as u32 as u64));
+                rng.next_f64() * 2f64 - 1f64
+            }
+        }
+    }
+}
+
+// from `p` to the cell [c, c + 1] along one axis, 0 inside it
+fn gap(p: f64, c: i32) -> f64 {
+    let (low, high) = (c as f64, (c + 1) as f64);
+    if p < low { low - p } else if p > high { p - high } else { 0f64 }
+}
@@ -0,0 +1,420 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// The player is a box standing on its feet, the camera sits at its eyes.
+// Each tick the box is swept against the solid blocks one axis at a time,
+// y first, so it slides along the walls instead of sticking to them.
+
+use std::num::{abs, cos, exp, sin};
+use std::f64::consts::PI;
+
+use input_manager::InputDatas;
+use key_bindings::{MoveForward, MoveBack, MoveLeft, MoveRight, Jump, Crouch, Sprint, CycleMoveMode};
+use math::{Aabb, Vec3};
+
+pub static HALF_WIDTH: f64          = 0.3;
+pub static HEIGHT: f64              = 1.8;
+pub static EYE_HEIGHT: f64          = 1.62;
+pub static CROUCH_EYE_HEIGHT: f64   = 1.27;
+// multiplies the camera's fov while sprinting
+pub static SPRINT_FOV_SCALE: f32    = 1.15;
+
+// in blocks per second
+static WALK_SPEED: f64              = 4.3;
+static SPRINT_SPEED: f64            = 5.6;
+static CROUCH_SPEED: f64            = 1.3;
+static SWIM_SPEED: f64              = 2.2;
+static FLY_SPEED: f64               = 12.;
+static JUMP_VELOCITY: f64           = 9.;
+static TERMINAL_VELOCITY: f64       = 78.;
+// in blocks per second per second
+static GRAVITY: f64                 = 32.;
+// in the fluids, the buoyancy leaves a bit of the gravity and the drag
+// slows everything down, per second
+static FLUID_GRAVITY: f64           = 8.;
+static SWIM_ACCELERATION: f64       = 20.;
+static FLUID_DRAG: f64              = 4.;
+// the half blocks the player walks onto, full blocks need a jump
+static STEP_HEIGHT: f64             = 0.6;
+// a crouching player keeps ground this close under the feet
+static EDGE_DEPTH: f64              = 0.5;
+static EDGE_STEP: f64               = 0.05;
+// share of the gap to the target eye height closed every tick
+static EYE_EASING: f64              = 0.5;
+// the view bobs once per stride, in blocks
+static BOB_STRIDE: f64              = 1.2;
+static BOB_HEIGHT: f64              = 0.06;
+
+#[deriving(Clone, Eq, ToStr)]
+pub enum MoveMode {
+    Walking,
+    // no gravity, the blocks still collide
+    Flying,
+    // no gravity and no collision
+    Noclip
+}
+
+impl MoveMode {
+    pub fn next(&self) -> MoveMode {
+        match *self {
+            Walking => Flying,
+            Flying  => Noclip,
+            Noclip  => Walking
+        }
+    }
+}
+
+// what a block is to the player
+#[deriving(Clone, Eq, ToStr)]
+pub enum Collision {
+    Passable,
+    Solid,
+    Liquid
+}
+
+pub struct Player {
+    // center of the feet
+    priv position:      Vec3<f64>,
+    priv velocity:      Vec3<f64>,
+    priv mode:          MoveMode,
+    priv on_ground:     bool,
+    // stopped by a wall on the last tick
+    priv against_wall:  bool,
+    priv in_fluid:      bool,
+    priv sprinting:     bool,
+    priv crouching:     bool,
+    // eases toward EYE_HEIGHT or CROUCH_EYE_HEIGHT
+    priv eye_height:    f64,
+    priv view_bobbing:  bool,
+    // distance walked on the ground and how much of the bob is shown
+    priv bob_distance:  f64,
+    priv bob_amount:    f64
+}
+
+impl Player {
+    pub fn new(position: Vec3<f64>) -> Player {
+        Player {
+            position:       position,
+            velocity:       Vec3::new(0f64, 0f64, 0f64),
+            mode:           Walking,
+            on_ground:      false,
+            against_wall:   false,
+            in_fluid:       false,
+            sprinting:      false,
+            crouching:      false,
+            eye_height:     EYE_HEIGHT,
+            view_bobbing:   true,
+            bob_distance:   0f64,
+            bob_amount:     0f64
+        }
+    }
+
+    pub fn get_position(&self) -> Vec3<f64> {
+        self.position.clone()
+    }
+
+    // teleports, the velocity is lost
+    pub fn set_position(&mut self, position: &Vec3<f64>) -> () {
+        self.position = position.clone();
+        self.velocity = Vec3::new(0f64, 0f64, 0f64);
+        self.on_ground = false;
+    }
+
+    pub fn get_eye_position(&self) -> Vec3<f32> {
+        let bob = if self.view_bobbing {
+            abs(sin(self.bob_distance * PI / BOB_STRIDE)) * BOB_HEIGHT * self.bob_amount
+        } else {
+            0f64
+        };
+        Vec3::new(self.position.x as f32, (self.position.y + self.eye_height + bob) as f32, self.position.z as f32)
+    }
+
+    // the eyes at standing height, no crouch nor bob, as set_eye_position
+    // takes them
+    pub fn get_standing_eye_position(&self) -> Vec3<f32> {
+        Vec3::new(self.position.x as f32, (self.position.y + EYE_HEIGHT) as f32, self.position.z as f32)
+    }
+
+    // the eyes at standing height
+    pub fn set_eye_position(&mut self, eye: &Vec3<f32>) -> () {
+        self.set_position(&Vec3::new(eye.x as f64, eye.y as f64 - EYE_HEIGHT, eye.z as f64));
+    }
+
+    pub fn get_velocity(&self) -> Vec3<f64> {
+        self.velocity.clone()
+    }
+
+    pub fn get_mode(&self) -> MoveMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: MoveMode) -> () {
+        self.mode = mode;
+        self.velocity = Vec3::new(0f64, 0f64, 0f64);
+    }
+
+    pub fn is_on_ground(&self) -> bool {
+        self.on_ground
+    }
+
+    pub fn is_in_fluid(&self) -> bool {
+        self.in_fluid
+    }
+
+    pub fn is_sprinting(&self) -> bool {
+        self.sprinting
+    }
+
+    pub fn is_crouching(&self) -> bool {
+        self.crouching
+    }
+
+    pub fn get_view_bobbing(&self) -> bool {
+        self.view_bobbing
+    }
+
+    pub fn set_view_bobbing(&mut self, view_bobbing: bool) -> () {
+        self.view_bobbing = view_bobbing;
+    }
+
+    pub fn get_aabb(&self) -> Aabb {
+        Aabb::new(Vec3::new(self.position.x - HALF_WIDTH, self.position.y, self.position.z - HALF_WIDTH),
+            Vec3::new(self.position.x + HALF_WIDTH, self.position.y + HEIGHT, self.position.z + HALF_WIDTH))
+    }
+
+    // every frame, the actions that happen once per key press
+    pub fn handle_inputs(&mut self, input_datas: &InputDatas) -> () {
+        if input_datas.was_pressed(CycleMoveMode) {
+            let mode = self.mode.next();
+            self.set_mode(mode);
+        }
+    }
+
+    // one simulation tick. `angles` are the camera's (horizontal, vertical)
+    // angles and `query` tells how each block stops the player
+    pub fn tick(&mut self,
+        input_datas: &InputDatas,
+        angles: (f32, f32),
+        delta_time: f32,
+        query: |i32, i32, i32| -> Collision) -> () {
+
+        let dt = delta_time as f64;
+        let (h, v) = angles;
+        let (h_angle, v_angle) = (h as f64, v as f64);
+        let forward = axis(input_datas.is_held(MoveForward), input_datas.is_held(MoveBack));
+        let strafe = axis(input_datas.is_held(MoveRight), input_datas.is_held(MoveLeft));
+        let jump = input_datas.is_held(Jump);
+        let crouch = input_datas.is_held(Crouch);
+
+        self.in_fluid = self.mode != Noclip && touches_liquid(&self.get_aabb(), |x, y, z| query(x, y, z));
+        self.crouching = self.mode == Walking && crouch && !self.in_fluid;
+        self.sprinting = input_datas.is_held(Sprint) && forward > 0f64 && !self.crouching && !self.in_fluid;
+
+        match self.mode {
+            Walking if self.in_fluid    => {
+                let (x, z) = horizontal(h_angle, forward, strafe);
+                self.velocity.x = x * SWIM_SPEED;
+                self.velocity.z = z * SWIM_SPEED;
+                self.velocity.y -= FLUID_GRAVITY * dt;
+                if jump {
+                    self.velocity.y += SWIM_ACCELERATION * dt;
+                    // climb out on the bank
+                    if self.against_wall {
+                        self.velocity.y = JUMP_VELOCITY;
+                    }
+                }
+                if crouch {
+                    self.velocity.y -= SWIM_ACCELERATION * dt;
+                }
+                self.velocity.y *= exp(-FLUID_DRAG * dt);
+            }
+            Walking                     => {
+                let speed = if self.crouching {
+                    CROUCH_SPEED
+                } else if self.sprinting {
+                    SPRINT_SPEED
+                } else {
+                    WALK_SPEED
+                };
+                let (x, z) = horizontal(h_angle, forward, strafe);
+                self.velocity.x = x * speed;
+                self.velocity.z = z * speed;
+                if jump && self.on_ground {
+                    self.velocity.y = JUMP_VELOCITY;
+                }
+                self.velocity.y -= GRAVITY * dt;
+                if self.velocity.y < -TERMINAL_VELOCITY {
+                    self.velocity.y = -TERMINAL_VELOCITY;
+                }
+            }
+            Flying                      => {
+                let (x, z) = horizontal(h_angle, forward, strafe);
+                let speed = if self.sprinting { FLY_SPEED * 2f64 } else { FLY_SPEED };
+                self.velocity = Vec3::new(x, axis(jump, crouch), z).scalar_product(speed);
+            }
+            // the old free fly, toward where the camera looks
+            Noclip                      => {
+                let dir = Vec3::new(cos(v_angle) * sin(h_angle), sin(v_angle), cos(v_angle) * cos(h_angle));
+                let (x, z) = horizontal(h_angle, 0f64, strafe);
+                let speed = if self.sprinting { FLY_SPEED * 2f64 } else { FLY_SPEED };
+                self.velocity = dir.scalar_product(forward).add_vec(&Vec3::new(x, axis(jump, crouch), z)).scalar_product(speed);
+            }
+        }
+
+        let d = self.velocity.scalar_product(dt);
+        let before = self.position.clone();
+        if self.mode == Noclip {
+            self.position = self.position.add_vec(&d);
+            self.on_ground = false;
+            self.against_wall = false;
+        } else {
+            self.move(d, query);
+        }
+        if self.against_wall {
+            self.sprinting = false;
+        }
+
+        let target = if self.crouching { CROUCH_EYE_HEIGHT } else { EYE_HEIGHT };
+        self.eye_height += (target - self.eye_height) * EYE_EASING;
+
+        // the view bobs while walking on the ground
+        let (dx, dz) = (self.position.x - before.x, self.position.z - before.z);
+        let walking = self.mode == Walking && self.on_ground && !self.in_fluid && dx * dx + dz * dz > 0f64;
+        self.bob_distance += (dx * dx + dz * dz).sqrt();
+        self.bob_amount += ((if walking { 1f64 } else { 0f64 }) - self.bob_amount) * EYE_EASING;
+    }
+
+    fn move(&mut self, d: Vec3<f64>, query: |i32, i32, i32| -> Collision) -> () {
+        let start = self.get_aabb();
+        // everything the box may touch on the way, stepping up and the
+        // ground under a crouching player included
+        let region = start.expand(d.x, d.y, d.z).expand(0f64, STEP_HEIGHT, 0f64).expand(0f64, -EDGE_DEPTH, 0f64);
+        let boxes = solid_boxes(&region, query);
+
+        let mut d = d;
+        if self.crouching && self.on_ground {
+            let (dx, dz) = edge_guard(boxes.as_slice(), &start, d.x, d.z);
+            d.x = dx;
+            d.z = dz;
+        }
+
+        let (mut end, mut moved) = sweep(boxes.as_slice(), &start, &d);
+        if self.mode == Walking && self.on_ground && (moved.x != d.x || moved.z != d.z) {
+            // up the ledge, across, and back down on it
+            let up = sweep_axis(boxes.as_slice(), &start, 1, STEP_HEIGHT);
+            let (stepped, step_moved) = sweep(boxes.as_slice(), &start.offset(0f64, up, 0f64), &Vec3::new(d.x, 0f64, d.z));
+            let down = sweep_axis(boxes.as_slice(), &stepped, 1, d.y - up);
+            if step_moved.x * step_moved.x + step_moved.z * step_moved.z > moved.x * moved.x + moved.z * moved.z {
+                end = stepped.offset(0f64, down, 0f64);
+                moved = Vec3::new(step_moved.x, up + down, step_moved.z);
+            }
+        }
+
+        self.on_ground = d.y < 0f64 && moved.y != d.y;
+        self.against_wall = moved.x != d.x || moved.z != d.z;
+        if moved.x != d.x { self.velocity.x = 0f64 }
+        if moved.y != d.y { self.velocity.y = 0f64 }
+        if moved.z != d.z { self.velocity.z = 0f64 }
+        self.position = Vec3::new(end.min.x + HALF_WIDTH, end.min.y, end.min.z + HALF_WIDTH);
+    }
+}
+
+// 1 for `positive`, -1 for `negative`, 0 for both or none
+fn axis(positive: bool, negative: bool) -> f64 {
+    (if positive { 1f64 } else { 0f64 }) - (if negative { 1f64 } else { 0f64 })
+}
+
+// the keys turned into a direction on the ground, same axis as the camera
+fn horizontal(h_angle: f64, forward: f64, strafe: f64) -> (f64, f64) {
+    let right = h_angle - PI / 2f64;
+    let x = sin(h_angle) * forward + sin(right) * strafe;
+    let z = cos(h_angle) * forward + cos(right) * strafe;
+    let len = (x * x + z * z).sqrt();
+    if len > 1f64 { (x / len, z / len) } else { (x, z) }
+}
+
+fn solid_boxes(region: &Aabb, query: |i32, i32, i32| -> Collision) -> ~[Aabb] {
+    let mut boxes = ~[];
+    for x in range(region.min.x.floor() as i32, region.max.x.floor() as i32 + 1) {
+        for y in range(region.min.y.floor() as i32, region.max.y.floor() as i32 + 1) {
+            for z in range(region.min.z.floor() as i32, region.max.z.floor() as i32 + 1) {
+                if query(x, y, z) == Solid {
+                    boxes.push(Aabb::block(x, y, z));
+                }
+            }
+        }
+    }
+    boxes
+}
+
+fn touches_liquid(b: &Aabb, query: |i32, i32, i32| -> Collision) -> bool {
+    for x in range(b.min.x.floor() as i32, b.max.x.floor() as i32 + 1) {
+        for y in range(b.min.y.floor() as i32, b.max.y.floor() as i32 + 1) {
+            for z in range(b.min.z.floor() as i32, b.max.z.floor() as i32 + 1) {
+                if query(x, y, z) == Liquid && Aabb::block(x, y, z).intersects(b) {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+// shortens the move so the box keeps some ground under it
+fn edge_guard(boxes: &[Aabb], start: &Aabb, dx: f64, dz: f64) -> (f64, f64) {
+    // a thin slab under the feet, the blocks beside the body are no ground
+    let feet = Aabb::new(Vec3::new(start.min.x, start.min.y - EDGE_DEPTH, start.min.z),
+        Vec3::new(start.max.x, start.min.y, start.max.z));
+    let supported = |x: f64, z: f64| {
+        let b = feet.offset(x, 0f64, z);
+        boxes.iter().any(|s| s.intersects(&b))
+    };
+    let (mut dx, mut dz) = (dx, dz);
+    while dx != 0f64 && !supported(dx, 0f64) {
+        dx = toward_zero(dx, EDGE_STEP);
+    }
+    while dz != 0f64 && !supported(0f64, dz) {
+        dz = toward_zero(dz, EDGE_STEP);
+    }
+    while dx != 0f64 && dz != 0f64 && !supported(dx, dz) {
+        dx = toward_zero(dx, EDGE_STEP);
+        dz = toward_zero(dz, EDGE_STEP);
+    }
+    (dx, dz)
+}
+
+fn toward_zero(value: f64, step: f64) -> f64 {
+    if abs(value) < step { 0f64 } else if value > 0f64 { value - step } else { value + step }
+}
+
+fn sweep_axis(boxes: &[Aabb], moving: &Aabb, axis: uint, d: f64) -> f64 {
+    boxes.iter().fold(d, |d, b| b.clip(moving, axis, d))
+}
+
+// the box moved by `d` along y, x then z, and how far it went
+fn sweep(boxes: &[Aabb], start: &Aabb, d: &Vec3<f64>) -> (Aabb, Vec3<f64>) {
+    let dy = sweep_axis(boxes, start, 1, d.y);
+    let b = start.offset_axis(1, dy);
+    let dx = sweep_axis(boxes, &b, 0, d.x);
+    let b = b.offset_axis(0, dx);
+    let dz = sweep_axis(boxes, &b, 2, d.z);
+    (b.offset_axis(2, dz), Vec3::new(dx, dy, dz))
+}
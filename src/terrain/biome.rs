@@ -0,0 +1,193 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// Biomes are picked from two low frequency noises, temperature and
+// humidity. Every biome sits at a point of that climate space: a column
+// takes the blocks of the closest biome, while its height is blended from
+// all the biomes weighted by their distance so the borders stay smooth.
+
+use std::num::exp;
+
+use math::prng::hash_seed;
+use noise::{NoiseSource, SimplexNoise};
+use noise::combinators::{Fbm, ScalePoint};
+use world::block::{BlockId, GRASS, DIRT, SAND, STONE_BRICK, HELLSAND, HELLROCK};
+
+pub type BiomeId = u8;
+
+pub static PLAINS: BiomeId      = 0;
+pub static DESERT: BiomeId      = 1;
+pub static FOREST: BiomeId      = 2;
+pub static MOUNTAINS: BiomeId   = 3;
+pub static WASTELAND: BiomeId   = 4;
+// only given by the generator's water pass, never by the climate
+pub static OCEAN: BiomeId       = 5;
+pub static RIVER: BiomeId       = 6;
+
+static CLIMATE_FREQUENCY: f64   = 0.002;
+// spread of the blending between biomes, in climate space
+static BLEND_WIDTH: f64         = 0.2;
+static CLIMATE_SALT: u64        = 0x636c696d617465;
+
+pub struct Biome {
+    name:               ~str,
+    temperature:        f64,
+    humidity:           f64,
+    // top block and the blocks under it
+    surface:            BlockId,
+    filler:             BlockId,
+    filler_depth:       i32,
+    // added to the base height, in blocks
+    height_offset:      f64,
+    // multiplies the height noise
+    height_scale:       f64,
+    // chance for a column to get a feature
+    decoration_density: f64,
+    // how much of the rivers are kept, 0 for none
+    rivers:             f64,
+    // false when the biome has no place in the climate space
+    climate:            bool
+}
+
+// what the generator needs to know about a column
+pub struct BiomeSample {
+    biome:          BiomeId,
+    height_offset:  f64,
+    height_scale:   f64,
+    rivers:         f64
+}
+
+pub struct BiomeMap {
+    priv temperature:   ~NoiseSource,
+    priv humidity:      ~NoiseSource,
+    priv biomes:        ~[Biome]
+}
+
+impl BiomeMap {
+    pub fn new(seed: u64) -> BiomeMap {
+        BiomeMap {
+            temperature:    climate_noise(hash_seed(seed, 1, 0, CLIMATE_SALT)),
+            humidity:       climate_noise(hash_seed(seed, 2, 0, CLIMATE_SALT)),
+            biomes:         default_biomes()
+        }
+    }
+
+    pub fn get<'a>(&'a self, id: BiomeId) -> &'a Biome {
+        &self.biomes[id as uint]
+    }
+
+    pub fn len(&self) -> uint {
+        self.biomes.len()
+    }
+
+    // (temperature, humidity) in [-1, 1]
+    pub fn climate(&self, x: i32, z: i32) -> (f64, f64) {
+        (self.temperature.get(x as f64, 0f64, z as f64), self.humidity.get(x as f64, 0f64, z as f64))
+    }
+
+    pub fn sample(&self, x: i32, z: i32) -> BiomeSample {
+        let (t, h) = self.climate(x, z);
+        let mut closest = 0u;
+        let mut closest_dist = Float::infinity();
+        let mut total = 0f64;
+        let mut offset = 0f64;
+        let mut scale = 0f64;
+        let mut rivers = 0f64;
+
+        for (i, b) in self.biomes.iter().enumerate() {
+            if !b.climate {
+                continue;
+            }
+            let d2 = (b.temperature - t) * (b.temperature - t) + (b.humidity - h) * (b.humidity - h);
+            if d2 < closest_dist {
+                closest_dist = d2;
+                closest = i;
+            }
+            let w = exp(-d2 / (BLEND_WIDTH * BLEND_WIDTH));
+            total += w;
+            offset += w * b.height_offset;
+            scale += w * b.height_scale;
+            rivers += w * b.rivers;
+        }
+
+        let closest_biome = &self.biomes[closest];
+        if total <= 0f64 {
+            // far from every biome, the weights underflowed
+            return BiomeSample {
+                biome:          closest as BiomeId,
+                height_offset:  closest_biome.height_offset,
+                height_scale:   closest_biome.height_scale,
+                rivers:         closest_biome.rivers
+            };
+        }
+        BiomeSample {
+            biome:          closest as BiomeId,
+            height_offset:  offset / total,
+            height_scale:   scale / total,
+            rivers:         rivers / total
+        }
+    }
+}
+
+// biome id from its name, as used in the presets
+pub fn find_biome(name: &str) -> Option<BiomeId> {
+    default_biomes().iter().position(|b| b.name.as_slice() == name).map(|i| i as BiomeId)
+}
+
+fn climate_noise(seed: u64) -> ~NoiseSource {
+    let base = ~SimplexNoise::<f64>::with_seed(seed) as ~NoiseSource;
+    let scaled = ~ScalePoint::new(base, CLIMATE_FREQUENCY, 1f64, CLIMATE_FREQUENCY) as ~NoiseSource;
+    ~Fbm::new(scaled, 3, 2f64, 0.5f64) as ~NoiseSource
+}
+
+// same order as the BiomeId statics
+fn default_biomes() -> ~[Biome] {
+    ~[
+        Biome { name: ~"plains", temperature: 0., humidity: 0.,
+            surface: GRASS, filler: DIRT, filler_depth: 3,
+            height_offset: 0., height_scale: 1., decoration_density: 0.005,
+            rivers: 1., climate: true },
+        Biome { name: ~"desert", temperature: 0.6, humidity: -0.5,
+            surface: SAND, filler: SAND, filler_depth: 4,
+            height_offset: -1., height_scale: 0.5, decoration_density: 0.001,
+            rivers: 0.2, climate: true },
+        Biome { name: ~"forest", temperature: 0.1, humidity: 0.5,
+            surface: GRASS, filler: DIRT, filler_depth: 3,
+            height_offset: 2., height_scale: 1.2, decoration_density: 0.03,
+            rivers: 1., climate: true },
+        Biome { name: ~"mountains", temperature: -0.5, humidity: 0.,
+            surface: STONE_BRICK, filler: STONE_BRICK, filler_depth: 1,
+            height_offset: 12., height_scale: 3., decoration_density: 0.002,
+            rivers: 0.6, climate: true },
+        Biome { name: ~"wasteland", temperature: 0.6, humidity: 0.5,
+            surface: HELLSAND, filler: HELLROCK, filler_depth: 4,
+            height_offset: 0., height_scale: 1.5, decoration_density: 0.004,
+            rivers: 0., climate: true },
+        Biome { name: ~"ocean", temperature: 0., humidity: 0.,
+            surface: SAND, filler: SAND, filler_depth: 3,
+            height_offset: 0., height_scale: 1., decoration_density: 0.,
+            rivers: 0., climate: false },
+        Biome { name: ~"river", temperature: 0., humidity: 0.,
+            surface: SAND, filler: DIRT, filler_depth: 2,
+            height_offset: 0., height_scale: 1., decoration_density: 0.,
+            rivers: 1., climate: false }
+    ]
+}
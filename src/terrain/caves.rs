@@ -0,0 +1,175 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// Two carvers dig the caves once the column is filled:
+//  - caverns, wherever a 3D noise goes over a threshold
+//  - worms, tunnels following a random walk steered by noise
+// A worm starts in a chunk but can cross into its neighbours, so each chunk
+// replays the worms of every chunk in WORM_RANGE and only keeps the blocks
+// falling inside itself. Everything depends on the seed and the positions,
+// so a cave continues across the chunk borders.
+
+use std::cmp::{max, min};
+use std::f64::consts::PI;
+
+use math::prng::{Prng, hash_seed};
+use noise::{NoiseSource, SimplexNoise};
+use noise::combinators::{Fbm, ScalePoint};
+use world::block::{BlockId, AIR, WATER};
+use world::chunk::{Chunk, CHUNK_SIZE, CHUNK_HEIGHT};
+
+static CAVERN_FREQUENCY: f64    = 0.04;
+static CAVERN_THRESHOLD: f64    = 0.45;
+// keep a roof over the caverns
+static CAVERN_MIN_DEPTH: i32    = 5;
+
+static WORM_RANGE: i32          = 4;
+static WORM_CHANCE: f64         = 0.3;
+static MAX_WORMS: i32           = 3;
+static WORM_MIN_Y: i32          = 8;
+static WORM_MAX_Y: i32          = 72;
+// one block per step, the longest worm must stay in WORM_RANGE chunks
+static WORM_MIN_LENGTH: i32     = 24;
+static WORM_MAX_LENGTH: i32     = 56;
+static WORM_STEER_FREQUENCY: f64 = 0.05;
+static WORM_TURN: f64           = 0.35;
+static WORM_CLIMB: f64          = 0.2;
+
+static CAVES_SALT: u64          = 0x6361766573;
+
+pub struct Caves {
+    priv seed:      u64,
+    priv cavern:    ~NoiseSource,
+    priv yaw:       ~NoiseSource,
+    priv pitch:     ~NoiseSource
+}
+
+impl Caves {
+    pub fn new(seed: u64) -> Caves {
+        let cavern_base = ~SimplexNoise::<f64>::with_seed(hash_seed(seed, 0, 0, CAVES_SALT)) as ~NoiseSource;
+        let cavern_scaled = ~ScalePoint::new(cavern_base, CAVERN_FREQUENCY, CAVERN_FREQUENCY * 1.5, CAVERN_FREQUENCY) as ~NoiseSource;
+        Caves {
+            seed:   seed,
+            cavern: ~Fbm::new(cavern_scaled, 2, 2f64, 0.5f64) as ~NoiseSource,
+            yaw:    steer_noise(hash_seed(seed, 1, 0, CAVES_SALT)),
+            pitch:  steer_noise(hash_seed(seed, 2, 0, CAVES_SALT))
+        }
+    }
+
+    // heights are the surface of each column, indexed by z * CHUNK_SIZE + x.
+    // `keep` (the bottom layer) and the water are never carved
+    pub fn carve(&self, chunk: &mut Chunk, heights: &[i32], keep: BlockId) -> () {
+        self.carve_caverns(chunk, heights, keep);
+        self.carve_worms(chunk, keep);
+    }
+
+    fn carve_caverns(&self, chunk: &mut Chunk, heights: &[i32], keep: BlockId) -> () {
+        let position = chunk.get_position();
+        for x in range(0, CHUNK_SIZE) {
+            for z in range(0, CHUNK_SIZE) {
+                let top = heights[(z * CHUNK_SIZE + x) as uint] - CAVERN_MIN_DEPTH;
+                let (wx, wz) = ((position.origin_x() + x) as f64, (position.origin_z() + z) as f64);
+                for y in range(1, top + 1) {
+                    if carvable(chunk.get(x, y, z), keep) && self.cavern.get(wx, y as f64, wz) > CAVERN_THRESHOLD {
+                        chunk.set(x, y, z, AIR);
+                    }
+                }
+            }
+        }
+    }
+
+    fn carve_worms(&self, chunk: &mut Chunk, keep: BlockId) -> () {
+        let position = chunk.get_position();
+        for cx in range(position.x - WORM_RANGE, position.x + WORM_RANGE + 1) {
+            for cz in range(position.z - WORM_RANGE, position.z + WORM_RANGE + 1) {
+                let mut rng = Prng::new(hash_seed(self.seed, cx, cz, CAVES_SALT));
+                if !rng.chance(WORM_CHANCE) {
+                    continue;
+                }
+                let count = rng.gen_range(1, MAX_WORMS + 1);
+                for _ in range(0, count) {
+                    self.walk_worm(&mut rng, cx * CHUNK_SIZE, cz * CHUNK_SIZE, chunk, keep);
+                }
+            }
+        }
+    }
+
+    // the whole walk is always computed, so that the rng stays in step
+    // whichever chunk is being carved
+    fn walk_worm(&self, rng: &mut Prng, origin_x: i32, origin_z: i32, chunk: &mut Chunk, keep: BlockId) -> () {
+        let mut x = origin_x as f64 + rng.next_f64() * CHUNK_SIZE as f64;
+        let mut y = rng.gen_range(WORM_MIN_Y, WORM_MAX_Y) as f64;
+        let mut z = origin_z as f64 + rng.next_f64() * CHUNK_SIZE as f64;
+        let mut yaw = rng.next_f64() * 2f64 * PI;
+        let mut pitch = (rng.next_f64() - 0.5) * 0.5;
+        let length = rng.gen_range(WORM_MIN_LENGTH, WORM_MAX_LENGTH);
+        let radius = 1.5 + rng.next_f64() * 1.5;
+
+        for step in range(0, length) {
+            yaw += self.yaw.get(x, y, z) * WORM_TURN;
+            pitch = pitch * 0.7 + self.pitch.get(x, y, z) * WORM_CLIMB;
+            x += yaw.cos() * pitch.cos();
+            y += pitch.sin();
+            z += yaw.sin() * pitch.cos();
+            // thinner at both ends
+            let r = radius * (0.5 + 0.5 * (PI * step as f64 / length as f64).sin());
+            carve_sphere(chunk, x, y, z, r, keep);
+        }
+    }
+}
+
+fn carvable(block: BlockId, keep: BlockId) -> bool {
+    block != keep && block != WATER
+}
+
+fn steer_noise(seed: u64) -> ~NoiseSource {
+    let base = ~SimplexNoise::<f64>::with_seed(seed) as ~NoiseSource;
+    ~ScalePoint::new(base, WORM_STEER_FREQUENCY, WORM_STEER_FREQUENCY, WORM_STEER_FREQUENCY) as ~NoiseSource
+}
+
+// only the part of the sphere inside the chunk is carved
+fn carve_sphere(chunk: &mut Chunk, cx: f64, cy: f64, cz: f64, radius: f64, keep: BlockId) -> () {
+    let position = chunk.get_position();
+    let (ox, oz) = (position.origin_x(), position.origin_z());
+    let min_x = max((cx - radius).floor() as i32 - ox, 0);
+    let max_x = min((cx + radius).floor() as i32 - ox, CHUNK_SIZE - 1);
+    let min_z = max((cz - radius).floor() as i32 - oz, 0);
+    let max_z = min((cz + radius).floor() as i32 - oz, CHUNK_SIZE - 1);
+    let min_y = max((cy - radius).floor() as i32, 1);
+    let max_y = min((cy + radius).floor() as i32, CHUNK_HEIGHT - 1);
+    if min_x > max_x || min_z > max_z || min_y > max_y {
+        return;
+    }
+
+    for x in range(min_x, max_x + 1) {
+        for z in range(min_z, max_z + 1) {
+            for y in range(min_y, max_y + 1) {
+                // distance from the block centre
+                let dx = (ox + x) as f64 + 0.5 - cx;
+                let dy = y as f64 + 0.5 - cy;
+                let dz = (oz + z) as f64 + 0.5 - cz;
+                if dx * dx + dy * dy + dz * dz < radius * radius && carvable(chunk.get(x, y, z), keep) {
+                    chunk.set(x, y, z, AIR);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,238 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// Erosion of the heightmap, before the blocks are filled in.
+// The world is covered by overlapping regions, centred every REGION_STRIDE
+// blocks and reaching REGION_STRIDE + REGION_PADDING blocks around their
+// centre. Each region is eroded on its own from its own seed, and a column
+// blends the changes of the four regions around it with weights falling to
+// zero at their edges, so there are no seams between the regions.
+//  - hydraulic: droplets roll down the slope, carry sediment away from the
+//    steep parts and drop it where they slow down, digging valleys and
+//    leaving fans at their end
+//  - thermal: material slides down from the slopes steeper than TALUS
+
+use std::cell::RefCell;
+use std::hashmap::HashMap;
+use std::rc::Rc;
+use std::vec;
+
+use math::prng::{Prng, hash_seed};
+use terrain::Generator;
+use world::chunk::div_floor;
+
+static REGION_STRIDE: i32       = 64;
+static REGION_PADDING: i32      = 8;
+static REGION_SIZE: i32         = 2 * (REGION_STRIDE + REGION_PADDING) + 1;
+// regions kept around, the cache is emptied past that
+static MAX_CACHED_REGIONS: uint = 64;
+static EROSION_SALT: u64        = 0x65726f73696f6e;
+
+static DROPLET_LIFETIME: uint   = 30;
+static INERTIA: f64             = 0.05;
+static CAPACITY: f64            = 4.;
+static MIN_CAPACITY: f64        = 0.01;
+static DEPOSITION: f64          = 0.3;
+static EROSION: f64             = 0.3;
+static EVAPORATION: f64         = 0.02;
+static GRAVITY: f64             = 4.;
+
+// height difference between neighbours past which material slides
+static TALUS: f64               = 1.2;
+static THERMAL_RATE: f64        = 0.25;
+
+pub struct ErosionSettings {
+    // droplets rolled on each region
+    droplets:           uint,
+    thermal_iterations: uint
+}
+
+pub struct Erosion {
+    priv settings:  ErosionSettings,
+    // height change of each region, REGION_SIZE x REGION_SIZE
+    priv cache:     RefCell<HashMap<(i32, i32), Rc<~[f64]>>>
+}
+
+impl Erosion {
+    pub fn new(settings: ErosionSettings) -> Erosion {
+        Erosion {
+            settings:   settings,
+            cache:      RefCell::new(HashMap::new())
+        }
+    }
+
+    // height change at the column, added to the generator's raw height
+    pub fn delta(&self, generator: &Generator, x: i32, z: i32) -> f64 {
+        let (rx, rz) = (div_floor(x, REGION_STRIDE), div_floor(z, REGION_STRIDE));
+        let fx = (x - rx * REGION_STRIDE) as f64 / REGION_STRIDE as f64;
+        let fz = (z - rz * REGION_STRIDE) as f64 / REGION_STRIDE as f64;
+        let mut delta = 0f64;
+        for &(cx, cz, w) in [(rx, rz, (1. - fx) * (1. - fz)), (rx + 1, rz, fx * (1. - fz)),
+                             (rx, rz + 1, (1. - fx) * fz), (rx + 1, rz + 1, fx * fz)].iter() {
+            if w <= 0. {
+                continue;
+            }
+            let region = self.get_region(generator, cx, cz);
+            let lx = x - (cx * REGION_STRIDE - REGION_STRIDE - REGION_PADDING);
+            let lz = z - (cz * REGION_STRIDE - REGION_STRIDE - REGION_PADDING);
+            delta += w * region.borrow()[(lz * REGION_SIZE + lx) as uint];
+        }
+        delta
+    }
+
+    fn get_region(&self, generator: &Generator, rx: i32, rz: i32) -> Rc<~[f64]> {
+        let cached = self.cache.with(|c| c.find(&(rx, rz)).map(|r| r.clone()));
+        match cached {
+            Some(r) => return r,
+            None    => {}
+        }
+        let region = Rc::new(self.erode_region(generator, rx, rz));
+        self.cache.with_mut(|c| {
+            if c.len() >= MAX_CACHED_REGIONS {
+                c.clear();
+            }
+            c.insert((rx, rz), region.clone());
+        });
+        region
+    }
+
+    fn erode_region(&self, generator: &Generator, rx: i32, rz: i32) -> ~[f64] {
+        let (ox, oz) = (rx * REGION_STRIDE - REGION_STRIDE - REGION_PADDING, rz * REGION_STRIDE - REGION_STRIDE - REGION_PADDING);
+        let mut original = vec::with_capacity((REGION_SIZE * REGION_SIZE) as uint);
+        for z in range(0, REGION_SIZE) {
+            for x in range(0, REGION_SIZE) {
+                original.push(generator.raw_height(ox + x, oz + z));
+            }
+        }
+        let mut map = original.clone();
+        let mut rng = Prng::new(hash_seed(generator.seed, rx, rz, EROSION_SALT));
+
+        for _ in range(0, self.settings.droplets) {
+            roll_droplet(map.as_mut_slice(), &mut rng);
+        }
+        for _ in range(0, self.settings.thermal_iterations) {
+            slide(map.as_mut_slice());
+        }
+
+        for i in range(0, map.len()) {
+            map[i] -= original[i];
+        }
+        map
+    }
+}
+
+fn height_and_gradient(map: &[f64], x: f64, z: f64) -> (f64, f64, f64) {
+    let (cx, cz) = (x.floor() as i32, z.floor() as i32);
+    let (u, v) = (x - cx as f64, z - cz as f64);
+    let i = (cz * REGION_SIZE + cx) as uint;
+    let (nw, ne, sw, se) = (map[i], map[i + 1], map[i + REGION_SIZE as uint], map[i + REGION_SIZE as uint + 1]);
+    let gx = (ne - nw) * (1. - v) + (se - sw) * v;
+    let gz = (sw - nw) * (1. - u) + (se - ne) * u;
+    let h = nw * (1. - u) * (1. - v) + ne * u * (1. - v) + sw * (1. - u) * v + se * u * v;
+    (h, gx, gz)
+}
+
+// spreads `amount` over the four cells around (x, z), negative to erode
+fn change(map: &mut [f64], x: f64, z: f64, amount: f64) -> () {
+    let (cx, cz) = (x.floor() as i32, z.floor() as i32);
+    let (u, v) = (x - cx as f64, z - cz as f64);
+    let i = (cz * REGION_SIZE + cx) as uint;
+    let row = REGION_SIZE as uint;
+    map[i] += amount * (1. - u) * (1. - v);
+    map[i + 1] += amount * u * (1. - v);
+    map[i + row] += amount * (1. - u) * v;
+    map[i + row + 1] += amount * u * v;
+}
+
+fn roll_droplet(map: &mut [f64], rng: &mut Prng) -> () {
+    let limit = (REGION_SIZE - 2) as f64;
+    let mut x = rng.next_f64() * limit;
+    let mut z = rng.next_f64() * limit;
+    let (mut dx, mut dz) = (0f64, 0f64);
+    let (mut speed, mut water, mut sediment) = (1f64, 1f64, 0f64);
+
+    for _ in range(0, DROPLET_LIFETIME) {
+        let (h, gx, gz) = height_and_gradient(map, x, z);
+        dx = dx * INERTIA - gx * (1. - INERTIA);
+        dz = dz * INERTIA - gz * (1. - INERTIA);
+        let len = (dx * dx + dz * dz).sqrt();
+        if len < 1e-9 {
+            break;
+        }
+        dx /= len;
+        dz /= len;
+        let (old_x, old_z) = (x, z);
+        x += dx;
+        z += dz;
+        if x < 0. || z < 0. || x >= limit || z >= limit {
+            break;
+        }
+
+        let (new_h, _, _) = height_and_gradient(map, x, z);
+        let dh = new_h - h;
+        let capacity = {
+            let c = -dh * speed * water * CAPACITY;
+            if c > MIN_CAPACITY { c } else { MIN_CAPACITY }
+        };
+        if sediment > capacity || dh > 0. {
+            // uphill, fill the hole behind at most
+            let amount = if dh > 0. {
+                if dh < sediment { dh } else { sediment }
+            } else {
+                (sediment - capacity) * DEPOSITION
+            };
+            sediment -= amount;
+            change(map, old_x, old_z, amount);
+        } else {
+            // never dig deeper than the step taken
+            let wanted = (capacity - sediment) * EROSION;
+            let amount = if wanted < -dh { wanted } else { -dh };
+            sediment += amount;
+            change(map, old_x, old_z, -amount);
+        }
+
+        let s2 = speed * speed + dh * GRAVITY;
+        speed = if s2 > 0. { s2.sqrt() } else { 0. };
+        water *= 1. - EVAPORATION;
+    }
+}
+
+fn slide(map: &mut [f64]) -> () {
+    let size = REGION_SIZE;
+    for z in range(1, size - 1) {
+        for x in range(1, size - 1) {
+            let i = (z * size + x) as uint;
+            // lowest neighbour
+            let mut lowest = i;
+            for &n in [i - 1, i + 1, i - size as uint, i + size as uint].iter() {
+                if map[n] < map[lowest] {
+                    lowest = n;
+                }
+            }
+            let diff = map[i] - map[lowest];
+            if lowest != i && diff > TALUS {
+                let moved = (diff - TALUS) * THERMAL_RATE;
+                map[i] -= moved;
+                map[lowest] += moved;
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// Decoration pass, multi-block features built from small templates.
+// Candidates sit on a jittered grid of `spacing` blocks and only depend on
+// the seed and on the generator's height and biome functions, never on the
+// chunk data. So when a chunk is generated it replays every candidate whose
+// template may reach it and writes its own share of the blocks: a tree on a
+// border is finished whenever the neighbour gets generated.
+
+use math::prng::{Prng, hash_seed};
+use terrain::Generator;
+use terrain::biome::BiomeId;
+use world::block::{BlockId, AIR, STONE_BRICK, LOG, LEAVES};
+use world::chunk::{Chunk, CHUNK_SIZE, CHUNK_HEIGHT, div_floor};
+
+// no template reaches further than this from its base column
+static MAX_RADIUS: i32      = 3;
+static FEATURES_SALT: u64   = 0x6665617475726573;
+
+#[deriving(Clone, Eq, ToStr)]
+pub enum Template {
+    Tree,
+    Boulder,
+    Pillar,
+    Ruin
+}
+
+impl Template {
+    pub fn from_name(name: &str) -> Option<Template> {
+        match name {
+            "tree"      => Some(Tree),
+            "boulder"   => Some(Boulder),
+            "pillar"    => Some(Pillar),
+            "ruin"      => Some(Ruin),
+            _           => None
+        }
+    }
+
+    // (dx, dy, dz, block) from the block above the surface
+    pub fn build(&self, rng: &mut Prng) -> ~[(i32, i32, i32, BlockId)] {
+        let mut blocks = ~[];
+        match *self {
+            Tree    => {
+                let height = rng.gen_range(4, 7);
+                for dy in range(height - 3, height + 1) {
+                    let r = if dy >= height - 1 { 1 } else { 2 };
+                    for dx in range(-r, r + 1) {
+                        for dz in range(-r, r + 1) {
+                            // ragged corners
+                            let corner = (dx == r || dx == -r) && (dz == r || dz == -r);
+                            if !(corner && rng.chance(0.5)) {
+                                blocks.push((dx, dy, dz, LEAVES));
+                            }
+                        }
+                    }
+                }
+                // the trunk goes last so it replaces the leaves
+                for dy in range(0, height) {
+                    blocks.push((0, dy, 0, LOG));
+                }
+            }
+            Boulder => {
+                let r = rng.gen_range(1, 3);
+                for dx in range(-r, r + 1) {
+                    for dy in range(-r, r + 1) {
+                        for dz in range(-r, r + 1) {
+                            if dx * dx + dy * dy + dz * dz <= r * r {
+                                blocks.push((dx, dy, dz, STONE_BRICK));
+                            }
+                        }
+                    }
+                }
+            }
+            Pillar  => {
+                for dy in range(0, rng.gen_range(3, 9)) {
+                    blocks.push((0, dy, 0, STONE_BRICK));
+                }
+            }
+            Ruin    => {
+                // broken walls around a 5x5 room
+                for dx in range(-2, 3) {
+                    for dz in range(-2, 3) {
+                        if dx != -2 && dx != 2 && dz != -2 && dz != 2 {
+                            continue;
+                        }
+                        for dy in range(0, rng.gen_range(0, 4)) {
+                            blocks.push((dx, dy, dz, STONE_BRICK));
+                        }
+                    }
+                }
+            }
+        }
+        blocks
+    }
+}
+
+pub struct Feature {
+    template:   Template,
+    // one candidate per spacing x spacing cell, at least spacing / 2 apart
+    spacing:    i32,
+    // largest height difference with the four neighbour columns
+    max_slope:  i32,
+    // chance for a cell to get the feature
+    chance:     f64,
+    // empty for any biome
+    biomes:     ~[BiomeId]
+}
+
+pub fn place_features(generator: &Generator, chunk: &mut Chunk) -> () {
+    let position = chunk.get_position();
+    let (ox, oz) = (position.origin_x(), position.origin_z());
+
+    for (i, feature) in generator.features.iter().enumerate() {
+        let s = feature.spacing;
+        let salt = FEATURES_SALT + i as u64;
+        for cx in range(div_floor(ox - MAX_RADIUS, s), div_floor(ox + CHUNK_SIZE - 1 + MAX_RADIUS, s) + 1) {
+            for cz in range(div_floor(oz - MAX_RADIUS, s), div_floor(oz + CHUNK_SIZE - 1 + MAX_RADIUS, s) + 1) {
+                let mut rng = Prng::new(hash_seed(generator.seed, cx, cz, salt));
+                let x = cx * s + rng.gen_range(0, s / 2 + 1);
+                let z = cz * s + rng.gen_range(0, s / 2 + 1);
+                if !accept(generator, feature, &mut rng, x, z) {
+                    continue;
+                }
+
+                let base_y = generator.height_at(x, z) + 1;
+                for &(dx, dy, dz, block) in feature.template.build(&mut rng).iter() {
+                    let (lx, y, lz) = (x + dx - ox, base_y + dy, z + dz - oz);
+                    if lx < 0 || lx >= CHUNK_SIZE || lz < 0 || lz >= CHUNK_SIZE || y < 1 || y >= CHUNK_HEIGHT {
+                        continue;
+                    }
+                    // never buries the terrain, leaves give way to the rest
+                    let current = chunk.get(lx, y, lz);
+                    if current == AIR || (current == LEAVES && block != LEAVES) {
+                        chunk.set(lx, y, lz, block);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn accept(generator: &Generator, feature: &Feature, rng: &mut Prng, x: i32, z: i32) -> bool {
+    let chance = match generator.get_biomes() {
+        Some(biomes)    => {
+            let biome = generator.biome_at(x, z);
+            if !feature.biomes.is_empty() && !feature.biomes.contains(&biome) {
+                return false;
+            }
+            // decoration_density is per column
+            let s = feature.spacing;
+            let density = biomes.get(biome).decoration_density;
+            feature.chance * (1f64 - (1f64 - density).powi(s * s))
+        }
+        None            => feature.chance
+    };
+    if !rng.chance(chance) {
+        return false;
+    }
+
+    let height = generator.height_at(x, z);
+    if height < generator.sea_level {
+        return false;
+    }
+    for &(nx, nz) in [(x - 1, z), (x + 1, z), (x, z - 1), (x, z + 1)].iter() {
+        let diff = generator.height_at(nx, nz) - height;
+        if diff > feature.max_slope || -diff > feature.max_slope {
+            return false;
+        }
+    }
+    true
+}
@@ -0,0 +1,307 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::rc::Rc;
+use std::vec;
+
+use noise::NoiseSource;
+use terrain::biome::{BiomeMap, BiomeId, PLAINS, OCEAN, RIVER, find_biome};
+use terrain::caves::Caves;
+use terrain::erosion::{Erosion, ErosionSettings};
+use terrain::features::{Feature, Template, place_features};
+use terrain::ores::{Ore, place_ores};
+use terrain::structures::{StructureSet, StructureKind, Structure};
+use terrain::water::Hydrology;
+use terrain::preset::Preset;
+use world::block::{BlockRegistry, BlockId, GRASS, SAND, WATER};
+use world::chunk::{Chunk, ChunkPos, CHUNK_SIZE, CHUNK_HEIGHT};
+
+pub mod biome;
+pub mod caves;
+pub mod erosion;
+pub mod features;
+pub mod ores;
+pub mod preset;
+pub mod structures;
+pub mod water;
+
+pub struct Generator {
+    priv seed:          u64,
+    priv preset_name:   ~str,
+    // in [-1, 1] for (x, 0, z)
+    priv height:        ~NoiseSource,
+    priv base_height:   f64,
+    priv height_scale:  f64,
+    priv sea_level:     i32,
+    priv layers:        ~[(BlockId, i32)],
+    priv filler:        BlockId,
+    priv bottom:        BlockId,
+    // None when the preset disables biomes
+    priv biomes:        Option<BiomeMap>,
+    priv caves:         Option<Caves>,
+    priv ores:          ~[Ore],
+    priv features:      ~[Feature],
+    priv structures:    StructureSet,
+    priv erosion:       Option<Erosion>,
+    priv water:         Option<Hydrology>
+}
+
+impl Generator {
+    // the same seed and preset always generate the same chunks
+    pub fn new(seed: u64, preset: &Preset, blocks: &BlockRegistry) -> Result<Generator, ~str> {
+        let find = |name: &str| -> Result<BlockId, ~str> {
+            match blocks.find(name) {
+                Some(id)    => Ok(id),
+                None        => Err(format!("preset {}: unknown block `{}`", preset.name, name))
+            }
+        };
+
+        let mut layers = ~[];
+        for &(ref name, depth) in preset.layers.iter() {
+            match find(name.as_slice()) {
+                Ok(id)  => layers.push((id, depth)),
+                Err(e)  => return Err(e)
+            }
+        }
+        let filler = match find(preset.filler.as_slice()) { Ok(id) => id, Err(e) => return Err(e) };
+        let bottom = match find(preset.bottom.as_slice()) { Ok(id) => id, Err(e) => return Err(e) };
+
+        let mut ores = ~[];
+        for rule in preset.ores.iter() {
+            let block = match find(rule.block.as_slice()) { Ok(id) => id, Err(e) => return Err(e) };
+            let mut hosts = ~[];
+            for name in rule.hosts.iter() {
+                match find(name.as_slice()) {
+                    Ok(id)  => hosts.push(id),
+                    Err(e)  => return Err(e)
+                }
+            }
+            ores.push(Ore {
+                block:      block,
+                min_y:      rule.min_y,
+                max_y:      rule.max_y,
+                vein_size:  rule.vein_size,
+                veins:      rule.veins,
+                hosts:      hosts
+            });
+        }
+
+        let mut features = ~[];
+        for rule in preset.features.iter() {
+            let template = match Template::from_name(rule.template.as_slice()) {
+                Some(t) => t,
+                None    => return Err(format!("preset {}: unknown feature `{}`", preset.name, rule.template))
+            };
+            let mut biomes = ~[];
+            for name in rule.biomes.iter() {
+                match find_biome(name.as_slice()) {
+                    Some(id)    => biomes.push(id),
+                    None        => return Err(format!("preset {}: unknown biome `{}`", preset.name, *name))
+                }
+            }
+            features.push(Feature {
+                template:   template,
+                spacing:    rule.spacing,
+                max_slope:  rule.max_slope,
+                chance:     rule.chance,
+                biomes:     biomes
+            });
+        }
+
+        let mut structures = ~[];
+        for &(ref name, chance) in preset.structures.iter() {
+            match StructureKind::from_name(name.as_slice()) {
+                Some(kind)  => structures.push((kind, chance)),
+                None        => return Err(format!("preset {}: unknown structure `{}`", preset.name, *name))
+            }
+        }
+
+        Ok(Generator {
+            seed:           seed,
+            preset_name:    preset.name.clone(),
+            height:         preset.make_noise(seed),
+            base_height:    preset.base_height,
+            height_scale:   preset.height_scale,
+            sea_level:      preset.sea_level,
+            layers:         layers,
+            filler:         filler,
+            bottom:         bottom,
+            biomes:         if preset.biomes { Some(BiomeMap::new(seed)) } else { None },
+            caves:          if preset.caves { Some(Caves::new(seed)) } else { None },
+            ores:           ores,
+            features:       features,
+            structures:     StructureSet::new(structures),
+            erosion:        match preset.erosion {
+                (0, 0)                          => None,
+                (droplets, thermal_iterations)  => Some(Erosion::new(ErosionSettings {
+                    droplets:           droplets,
+                    thermal_iterations: thermal_iterations
+                }))
+            },
+            water:          if preset.oceans || preset.rivers {
+                Some(Hydrology::new(seed, preset.oceans, preset.rivers))
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn get_preset_name(&self) -> ~str {
+        self.preset_name.clone()
+    }
+
+    pub fn get_sea_level(&self) -> i32 {
+        self.sea_level
+    }
+
+    pub fn get_biomes<'a>(&'a self) -> Option<&'a BiomeMap> {
+        self.biomes.as_ref()
+    }
+
+    pub fn biome_at(&self, x: i32, z: i32) -> BiomeId {
+        let (_, biome) = self.column(x, z);
+        biome
+    }
+
+    // the structure with a piece around the block, if any
+    pub fn structure_at(&self, x: i32, y: i32, z: i32) -> Option<Rc<Structure>> {
+        self.structures.structure_at(self, x, y, z)
+    }
+
+    pub fn height_at(&self, x: i32, z: i32) -> i32 {
+        let h = match self.erosion {
+            Some(ref erosion)   => self.raw_height(x, z) + erosion.delta(self, x, z),
+            None                => self.raw_height(x, z)
+        };
+        clamp_height(h.floor() as i32)
+    }
+
+    // surface height before the erosion
+    fn raw_height(&self, x: i32, z: i32) -> f64 {
+        let (height, _) = self.column(x, z);
+        height
+    }
+
+    // raw height and biome of a column. the biomes shift the base height and
+    // stretch the noise, then the oceans and the rivers dig in
+    fn column(&self, x: i32, z: i32) -> (f64, BiomeId) {
+        let noise = self.height.get(x as f64, 0f64, z as f64);
+        let (height, biome, rivers) = match self.biomes {
+            Some(ref biomes)    => {
+                let sample = biomes.sample(x, z);
+                (self.base_height + sample.height_offset + noise * self.height_scale * sample.height_scale,
+                 sample.biome, sample.rivers)
+            }
+            None                => (self.base_height + noise * self.height_scale, PLAINS, 1f64)
+        };
+        match self.water {
+            Some(ref water) => {
+                let sample = water.sample(x, z, rivers);
+                let shaped = water.shape(height, self.sea_level as f64, &sample);
+                let biome = if self.biomes.is_none() {
+                    biome
+                } else if sample.ocean > 0.5 {
+                    OCEAN
+                } else if sample.bank {
+                    RIVER
+                } else {
+                    biome
+                };
+                (shaped, biome)
+            }
+            None            => (height, biome)
+        }
+    }
+
+    pub fn generate(&self, position: &ChunkPos) -> ~Chunk {
+        let mut chunk = ~Chunk::new(position.clone());
+        let mut heights = vec::from_elem((CHUNK_SIZE * CHUNK_SIZE) as uint, 0i32);
+
+        for x in range(0, CHUNK_SIZE) {
+            for z in range(0, CHUNK_SIZE) {
+                let (wx, wz) = (position.origin_x() + x, position.origin_z() + z);
+                let height = self.height_at(wx, wz);
+                chunk.set(x, 0, z, self.bottom);
+                match self.biomes {
+                    Some(ref biomes)    => {
+                        let id = self.biome_at(wx, wz);
+                        let biome = biomes.get(id);
+                        for y in range(1, height + 1) {
+                            let depth = height - y;
+                            let block = if depth == 0 {
+                                // no grass under the water
+                                if biome.surface == GRASS && height < self.sea_level { SAND } else { biome.surface }
+                            } else if depth <= biome.filler_depth {
+                                biome.filler
+                            } else {
+                                self.filler
+                            };
+                            chunk.set(x, y, z, block);
+                        }
+                        chunk.set_biome(x, z, id);
+                    }
+                    None                => {
+                        for y in range(1, height + 1) {
+                            chunk.set(x, y, z, self.block_at_depth(height - y));
+                        }
+                    }
+                }
+                // the sea floods everything under its level
+                for y in range(height + 1, self.sea_level) {
+                    chunk.set(x, y, z, WATER);
+                }
+                heights[(z * CHUNK_SIZE + x) as uint] = height;
+            }
+        }
+
+        match self.caves {
+            Some(ref caves) => caves.carve(&mut *chunk, heights.as_slice(), self.bottom),
+            None            => {}
+        }
+        place_ores(self.seed, &mut *chunk, self.ores.as_slice());
+        place_features(self, &mut *chunk);
+        self.structures.place(self, &mut *chunk);
+
+        // a freshly generated chunk can be generated again, no need to save it
+        chunk.set_modified(false);
+        chunk
+    }
+
+    // block `depth` blocks under the surface
+    fn block_at_depth(&self, depth: i32) -> BlockId {
+        let mut top = 0;
+        for &(block, thickness) in self.layers.iter() {
+            if depth < top + thickness {
+                return block;
+            }
+            top += thickness;
+        }
+        self.filler
+    }
+}
+
+fn clamp_height(height: i32) -> i32 {
+    if height < 0 { 0 } else if height > CHUNK_HEIGHT - 1 { CHUNK_HEIGHT - 1 } else { height }
+}
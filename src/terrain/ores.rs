@@ -0,0 +1,78 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// Ore veins, placed after the caves. Each vein is a short random walk
+// replacing the host blocks it goes through, it never leaves its chunk.
+
+use std::cmp::{max, min};
+
+use math::prng::{Prng, hash_seed};
+use world::block::BlockId;
+use world::chunk::{Chunk, CHUNK_SIZE, CHUNK_HEIGHT};
+
+static ORES_SALT: u64 = 0x6f726573;
+
+pub struct Ore {
+    block:      BlockId,
+    // inclusive
+    min_y:      i32,
+    max_y:      i32,
+    // blocks per vein
+    vein_size:  i32,
+    veins:      i32,
+    // blocks the ore may replace
+    hosts:      ~[BlockId]
+}
+
+// the ores are placed in order, a later ore may replace an earlier one
+// if it is listed as host
+pub fn place_ores(seed: u64, chunk: &mut Chunk, ores: &[Ore]) -> () {
+    let position = chunk.get_position();
+    let mut rng = Prng::new(hash_seed(seed, position.x, position.z, ORES_SALT));
+
+    for ore in ores.iter() {
+        for _ in range(0, ore.veins) {
+            let mut x = rng.gen_range(0, CHUNK_SIZE);
+            let mut y = rng.gen_range(max(ore.min_y, 1), min(ore.max_y, CHUNK_HEIGHT - 1) + 1);
+            let mut z = rng.gen_range(0, CHUNK_SIZE);
+            for _ in range(0, ore.vein_size) {
+                if ore.hosts.contains(&chunk.get(x, y, z)) {
+                    chunk.set(x, y, z, ore.block);
+                }
+                match rng.gen_range(0, 6) {
+                    0 => x += 1,
+                    1 => x -= 1,
+                    2 => y += 1,
+                    3 => y -= 1,
+                    4 => z += 1,
+                    _ => z -= 1
+                }
+                x = clamp(x, 0, CHUNK_SIZE - 1);
+                y = clamp(y, max(ore.min_y, 1), min(ore.max_y, CHUNK_HEIGHT - 1));
+                z = clamp(z, 0, CHUNK_SIZE - 1);
+            }
+        }
+    }
+}
+
+fn clamp(v: i32, low: i32, high: i32) -> i32 {
+    if v < low { low } else if v > high { high } else { v }
+}
@@ -0,0 +1,470 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// Generator presets, text files of `key = value` lines, `#` starts a comment:
+//
+//   name = default
+//   noise = (scale_point (perlin) 0.01 1 0.05)
+//   base_height = 5
+//   height_scale = 5
+//   sea_level = 0
+//   layer = dirt 3
+//   filler = stone_brick
+//   bottom = bedrock
+//   biomes = true
+//   caves = true
+//   oceans = true
+//   rivers = true
+//   ore = iron_ore 1 64 8 6 stone_brick
+//   feature = tree 8 1 1 plains forest
+//   structure = dungeon 0.5
+//   erosion = 3000 20
+//
+// The noise graph is a s-expression, in [-1, 1], giving the surface height
+// base_height + noise * height_scale, the columns under sea_level are
+// flooded. `layer` and `feature` can be repeated, layers are listed from
+// the surface down. `ore` lines are
+// `block min_y max_y vein_size veins_per_chunk host...` and can be repeated.
+// `feature` lines are `template spacing max_slope chance biome...`, the
+// templates are tree, boulder, pillar and ruin, no biome means any biome.
+// `structure` lines are `village|dungeon|fortress chance`, the chance for
+// each cell of the world to get that structure. `erosion` is the number of
+// droplets rolled on every region then the thermal erosion iterations.
+// With biomes on, the biome blocks replace the layers and the biomes
+// change the height.
+//
+// Noise nodes:
+//   (perlin) (simplex) (worley euclidean|manhattan|chebyshev f1|f2|f2-f1|cell) (constant v)
+//   (scale_point src sx sy sz) (scale_bias src scale bias) (clamp src low high) (add a b)
+//   (fbm src octaves lacunarity gain) (billow ...) (turbulence ...) (ridged src octaves lacunarity gain offset)
+//   (warp src warp_x warp_y warp_z strength) (select control low high threshold falloff) (blend a b control)
+
+use std::io::File;
+use std::str;
+
+use math::prng::hash_seed;
+use noise::{NoiseSource, PerlinNoise, SimplexNoise, WorleyNoise};
+use noise::combinators::{Constant, ScalePoint, ScaleBias, Clamp, Add, Fbm, Billow, 
+    Turbulence, Ridged, DomainWarp, Select, Blend};
+use noise::worley::{Euclidean, Manhattan, Chebyshev, F1, F2, F2MinusF1, CellId};
+
+static NOISE_SALT: u64 = 0x6e6f697365;
+
+macro_rules! try(
+    ($e:expr) => (match $e { Ok(v) => v, Err(e) => return Err(e) })
+)
+
+#[deriving(Clone, ToStr)]
+pub enum Expr {
+    Atom(~str),
+    List(~[Expr])
+}
+
+#[deriving(Clone)]
+pub struct OreRule {
+    block:      ~str,
+    min_y:      i32,
+    max_y:      i32,
+    vein_size:  i32,
+    veins:      i32,
+    hosts:      ~[~str]
+}
+
+#[deriving(Clone)]
+pub struct FeatureRule {
+    template:   ~str,
+    spacing:    i32,
+    max_slope:  i32,
+    chance:     f64,
+    biomes:     ~[~str]
+}
+
+#[deriving(Clone)]
+pub struct Preset {
+    name:           ~str,
+    noise:          Expr,
+    base_height:    f64,
+    height_scale:   f64,
+    sea_level:      i32,
+    // (block name, depth) from the surface down
+    layers:         ~[(~str, i32)],
+    filler:         ~str,
+    bottom:         ~str,
+    biomes:         bool,
+    caves:          bool,
+    oceans:         bool,
+    rivers:         bool,
+    ores:           ~[OreRule],
+    features:       ~[FeatureRule],
+    // (kind, chance)
+    structures:     ~[(~str, f64)],
+    // (droplets, thermal iterations), none by default
+    erosion:        (uint, uint)
+}
+
+impl Preset {
+    // a built-in preset name from ./presets or the path of a preset file
+    pub fn load(name: &str) -> Result<Preset, ~str> {
+        let path = if name.ends_with(".preset") {
+            Path::new(name)
+        } else {
+            Path::new(format!("./presets/{}.preset", name))
+        };
+        if !path.exists() {
+            return Err(format!("Cannot find preset: {}", path.display()));
+        }
+        Preset::parse(File::open(&path).read_to_str())
+    }
+
+    pub fn parse(text: &str) -> Result<Preset, ~str> {
+        let mut preset = Preset {
+            name:           ~"unnamed",
+            noise:          List(~[Atom(~"constant"), Atom(~"0")]),
+            base_height:    0f64,
+            height_scale:   1f64,
+            sea_level:      0,
+            layers:         ~[],
+            filler:         ~"stone_brick",
+            bottom:         ~"bedrock",
+            biomes:         false,
+            caves:          false,
+            oceans:         false,
+            rivers:         false,
+            ores:           ~[],
+            features:       ~[],
+            structures:     ~[],
+            erosion:        (0, 0)
+        };
+
+        for (n, raw_line) in text.lines().enumerate() {
+            let line = match raw_line.find('#') {
+                Some(i) => raw_line.slice_to(i).trim(),
+                None    => raw_line.trim()
+            };
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = match line.find('=') {
+                Some(i) => (line.slice_to(i).trim(), line.slice_from(i + 1).trim()),
+                None    => return Err(format!("line {}: expected `key = value`", n + 1))
+            };
+            let err = |msg: &str| -> ~str { format!("line {}: {}: {}", n + 1, key, msg) };
+
+            match key {
+                "name"          => preset.name = value.to_owned(),
+                "noise"         => preset.noise = match parse_expr(value) {
+                    Ok(e)   => e,
+                    Err(e)  => return Err(err(e.as_slice()))
+                },
+                "base_height"   => preset.base_height = match from_str::<f64>(value) {
+                    Some(v) => v,
+                    None    => return Err(err("expected a number"))
+                },
+                "height_scale"  => preset.height_scale = match from_str::<f64>(value) {
+                    Some(v) => v,
+                    None    => return Err(err("expected a number"))
+                },
+                "sea_level"     => preset.sea_level = match from_str::<i32>(value) {
+                    Some(v) => v,
+                    None    => return Err(err("expected an integer"))
+                },
+                "layer"         => {
+                    let words: ~[&str] = value.words().collect();
+                    match (words.len(), words.get_opt(1).and_then(|d| from_str::<i32>(*d))) {
+                        (2, Some(depth))    => preset.layers.push((words[0].to_owned(), depth)),
+                        _                   => return Err(err("expected `block depth`"))
+                    }
+                }
+                "filler"        => preset.filler = value.to_owned(),
+                "bottom"        => preset.bottom = value.to_owned(),
+                "biomes"        => preset.biomes = match from_str::<bool>(value) {
+                    Some(v) => v,
+                    None    => return Err(err("expected true or false"))
+                },
+                "caves"         => preset.caves = match from_str::<bool>(value) {
+                    Some(v) => v,
+                    None    => return Err(err("expected true or false"))
+                },
+                "oceans"        => preset.oceans = match from_str::<bool>(value) {
+                    Some(v) => v,
+                    None    => return Err(err("expected true or false"))
+                },
+                "rivers"        => preset.rivers = match from_str::<bool>(value) {
+                    Some(v) => v,
+                    None    => return Err(err("expected true or false"))
+                },
+                "ore"           => preset.ores.push(match parse_ore(value) {
+                    Some(o) => o,
+                    None    => return Err(err("expected `block min_y max_y vein_size veins host...`"))
+                }),
+                "feature"       => preset.features.push(match parse_feature(value) {
+                    Some(f) => f,
+                    None    => return Err(err("expected `template spacing max_slope chance biome...`"))
+                }),
+                "structure"     => {
+                    let words: ~[&str] = value.words().collect();
+                    match (words.len(), words.get_opt(1).and_then(|c| from_str::<f64>(*c))) {
+                        (2, Some(chance))   => preset.structures.push((words[0].to_owned(), chance)),
+                        _                   => return Err(err("expected `kind chance`"))
+                    }
+                }
+                "erosion"       => {
+                    let numbers: ~[Option<uint>] = value.words().map(|w| from_str::<uint>(w)).collect();
+                    match numbers.as_slice() {
+                        [Some(droplets), Some(thermal)] => preset.erosion = (droplets, thermal),
+                        _                               => return Err(err("expected `droplets thermal_iterations`"))
+                    }
+                }
+                _               => return Err(err("unknown key"))
+            }
+        }
+
+        // check the noise graph now rather than when a world is created
+        match build_noise(&preset.noise, &mut 0u64, 0) {
+            Ok(_)   => Ok(preset),
+            Err(e)  => Err(format!("noise: {}", e))
+        }
+    }
+
+    // each generator of the graph gets its own seed, derived from the world seed
+    pub fn make_noise(&self, seed: u64) -> ~NoiseSource {
+        build_noise(&self.noise, &mut 0u64, seed).unwrap()
+    }
+}
+
+fn parse_ore(value: &str) -> Option<OreRule> {
+    let words: ~[&str] = value.words().collect();
+    if words.len() < 6 {
+        return None;
+    }
+    let numbers: ~[i32] = words.slice(1, 5).iter().filter_map(|w| from_str::<i32>(*w)).collect();
+    if numbers.len() != 4 {
+        return None;
+    }
+    Some(OreRule {
+        block:      words[0].to_owned(),
+        min_y:      numbers[0],
+        max_y:      numbers[1],
+        vein_size:  numbers[2],
+        veins:      numbers[3],
+        hosts:      words.slice_from(5).iter().map(|w| w.to_owned()).collect()
+    })
+}
+
+fn parse_feature(value: &str) -> Option<FeatureRule> {
+    let words: ~[&str] = value.words().collect();
+    if words.len() < 4 {
+        return None;
+    }
+    match (from_str::<i32>(words[1]), from_str::<i32>(words[2]), from_str::<f64>(words[3])) {
+        (Some(spacing), Some(max_slope), Some(chance)) if spacing > 0 => Some(FeatureRule {
+            template:   words[0].to_owned(),
+            spacing:    spacing,
+            max_slope:  max_slope,
+            chance:     chance,
+            biomes:     words.slice_from(4).iter().map(|w| w.to_owned()).collect()
+        }),
+        _                                                               => None
+    }
+}
+
+pub fn parse_expr(text: &str) -> Result<Expr, ~str> {
+    let tokens = tokenize(text);
+    let mut pos = 0u;
+    let expr = try!(parse_tokens(tokens.as_slice(), &mut pos));
+    if pos != tokens.len() {
+        return Err(format!("unexpected `{}`", tokens[pos]));
+    }
+    Ok(expr)
+}
+
+fn tokenize(text: &str) -> ~[~str] {
+    let mut tokens = ~[];
+    let mut current = ~"";
+    for c in text.chars() {
+        if c == '(' || c == ')' || c.is_whitespace() {
+            if !current.is_empty() {
+                tokens.push(current.clone());
+                current = ~"";
+            }
+            if !c.is_whitespace() {
+                tokens.push(str::from_char(c));
+            }
+        } else {
+            current.push_char(c);
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn parse_tokens(tokens: &[~str], pos: &mut uint) -> Result<Expr, ~str> {
+    if *pos >= tokens.len() {
+        return Err(~"unexpected end of expression");
+    }
+    let token = tokens[*pos].clone();
+    *pos += 1;
+    if token == ~"(" {
+        let mut items = ~[];
+        loop {
+            if *pos >= tokens.len() {
+                return Err(~"missing `)`");
+            }
+            if tokens[*pos] == ~")" {
+                *pos += 1;
+                return Ok(List(items));
+            }
+            items.push(try!(parse_tokens(tokens, pos)));
+        }
+    } else if token == ~")" {
+        Err(~"unexpected `)`")
+    } else {
+        Ok(Atom(token))
+    }
+}
+
+fn build_noise(expr: &Expr, counter: &mut u64, seed: u64) -> Result<~NoiseSource, ~str> {
+    let items = match *expr {
+        List(ref items) if !items.is_empty()    => items,
+        _                                       => return Err(format!("expected a noise node, found {}", expr.to_str()))
+    };
+    let a = match items[0] {
+        Atom(ref name)  => Args { name: name.as_slice(), items: items.slice_from(1), seed: seed },
+        _               => return Err(~"a noise node must start with its name")
+    };
+
+    match arity(a.name) {
+        Some(n) if a.items.len() > n    => return Err(format!("{}: expected {} arguments, found {}", a.name, n, a.items.len())),
+        _                               => {}
+    }
+
+    let node = match a.name {
+        "perlin"        => ~PerlinNoise::<f64>::with_seed(leaf_seed(seed, counter)) as ~NoiseSource,
+        "simplex"       => ~SimplexNoise::<f64>::with_seed(leaf_seed(seed, counter)) as ~NoiseSource,
+        "worley"        => {
+            let metric = match try!(a.word(0)).as_slice() {
+                "euclidean" => Euclidean,
+                "manhattan" => Manhattan,
+                "chebyshev" => Chebyshev,
+                m           => return Err(format!("worley: unknown metric `{}`", m))
+            };
+            let mode = match try!(a.word(1)).as_slice() {
+                "f1"        => F1,
+                "f2"        => F2,
+                "f2-f1"     => F2MinusF1,
+                "cell"      => CellId,
+                m           => return Err(format!("worley: unknown mode `{}`", m))
+            };
+            ~WorleyNoise::with_settings(leaf_seed(seed, counter), metric, mode) as ~NoiseSource
+        }
+        "constant"      => ~Constant::new(try!(a.num(0))) as ~NoiseSource,
+        "scale_point"   => ~ScalePoint::new(try!(a.noise(0, counter)), 
+                                try!(a.num(1)), try!(a.num(2)), try!(a.num(3))) as ~NoiseSource,
+        "scale_bias"    => ~ScaleBias::new(try!(a.noise(0, counter)), 
+                                try!(a.num(1)), try!(a.num(2))) as ~NoiseSource,
+        "clamp"         => ~Clamp::new(try!(a.noise(0, counter)), 
+                                try!(a.num(1)), try!(a.num(2))) as ~NoiseSource,
+        "add"           => ~Add::new(try!(a.noise(0, counter)), try!(a.noise(1, counter))) as ~NoiseSource,
+        "fbm"           => ~Fbm::new(try!(a.noise(0, counter)), 
+                                try!(a.count(1)), try!(a.num(2)), try!(a.num(3))) as ~NoiseSource,
+        "billow"        => ~Billow::new(try!(a.noise(0, counter)), 
+                                try!(a.count(1)), try!(a.num(2)), try!(a.num(3))) as ~NoiseSource,
+        "turbulence"    => ~Turbulence::new(try!(a.noise(0, counter)), 
+                                try!(a.count(1)), try!(a.num(2)), try!(a.num(3))) as ~NoiseSource,
+        "ridged"        => ~Ridged::new(try!(a.noise(0, counter)), 
+                                try!(a.count(1)), try!(a.num(2)), try!(a.num(3)), try!(a.num(4))) as ~NoiseSource,
+        "warp"          => ~DomainWarp::new(try!(a.noise(0, counter)), 
+                                try!(a.noise(1, counter)), try!(a.noise(2, counter)), try!(a.noise(3, counter)), 
+                                try!(a.num(4))) as ~NoiseSource,
+        "select"        => ~Select::new(try!(a.noise(0, counter)), 
+                                try!(a.noise(1, counter)), try!(a.noise(2, counter)), 
+                                try!(a.num(3)), try!(a.num(4))) as ~NoiseSource,
+        "blend"         => ~Blend::new(try!(a.noise(0, counter)), 
+                                try!(a.noise(1, counter)), try!(a.noise(2, counter))) as ~NoiseSource,
+        _               => return Err(format!("unknown noise node `{}`", a.name))
+    };
+    Ok(node)
+}
+
+// arguments count of each noise node, None for the unknown ones
+fn arity(name: &str) -> Option<uint> {
+    match name {
+        "perlin" | "simplex"                        => Some(0),
+        "constant"                                  => Some(1),
+        "worley" | "add"                            => Some(2),
+        "scale_bias" | "clamp" | "blend"            => Some(3),
+        "scale_point" | "fbm" | "billow"
+            | "turbulence"                          => Some(4),
+        "ridged" | "warp" | "select"                => Some(5),
+        _                                           => None
+    }
+}
+
+fn leaf_seed(seed: u64, counter: &mut u64) -> u64 {
+    *counter += 1;
+    hash_seed(seed, *counter as i32, 0, NOISE_SALT)
+}
+
+// arguments of a noise node
+struct Args<'a> {
+    name:   &'a str,
+    items:  &'a [Expr],
+    seed:   u64
+}
+
+impl<'a> Args<'a> {
+    fn noise(&self, i: uint, counter: &mut u64) -> Result<~NoiseSource, ~str> {
+        if i >= self.items.len() {
+            Err(format!("{}: missing argument {}", self.name, i + 1))
+        } else {
+            build_noise(&self.items[i], counter, self.seed)
+        }
+    }
+
+    fn num(&self, i: uint) -> Result<f64, ~str> {
+        match self.items.get_opt(i) {
+            Some(&Atom(ref v))  => match from_str::<f64>(v.as_slice()) {
+                Some(n) => Ok(n),
+                None    => Err(format!("{}: `{}` is not a number", self.name, *v))
+            },
+            _                   => Err(format!("{}: argument {} must be a number", self.name, i + 1))
+        }
+    }
+
+    // a positive integer, e.g. a number of octaves
+    fn count(&self, i: uint) -> Result<uint, ~str> {
+        let n = try!(self.num(i));
+        if n < 1f64 || n != n.floor() {
+            Err(format!("{}: argument {} must be a positive integer, found {}", self.name, i + 1, n))
+        } else {
+            Ok(n as uint)
+        }
+    }
+
+    fn word(&self, i: uint) -> Result<~str, ~str> {
+        match self.items.get_opt(i) {
+            Some(&Atom(ref v))  => Ok(v.clone()),
+            _                   => Err(format!("{}: argument {} must be a name", self.name, i + 1))
+        }
+    }
+}
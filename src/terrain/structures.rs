@@ -0,0 +1,458 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// Jigsaw structures: villages, dungeons and fortresses assembled from piece
+// templates. Every piece is a box with exits in the middle of some of its
+// walls, a new piece is snapped on an open exit when it has the opposite
+// exit and fits without overlapping the others. The world is split in
+// cells of STRUCTURE_CELL blocks holding at most one structure, which never
+// leaves its cell: a chunk only has to look at the structure of its cell.
+// Structures are assembled from the cell's seed and cached.
+
+use std::cell::RefCell;
+use std::hashmap::HashMap;
+use std::rc::Rc;
+
+use math::prng::{Prng, hash_seed};
+use terrain::Generator;
+use world::block::{BlockId, AIR, STONE_BRICK, HELLROCK, HELLSAND, SAND, DIRT, LOG, LIGHTGEM};
+use world::chunk::{Chunk, CHUNK_SIZE, CHUNK_HEIGHT, div_floor};
+
+pub static STRUCTURE_CELL: i32  = 128;
+// from the start of the structure, so it stays in its cell
+static MAX_EXTENT: i32          = 48;
+static PLACE_ATTEMPTS: uint     = 3;
+static DUNGEON_DEPTH: i32       = 24;
+static STRUCTURES_SALT: u64     = 0x737472756374;
+// cells kept around, the cache is emptied past that
+static MAX_CACHED_CELLS: uint   = 64;
+
+#[deriving(Clone, Eq, ToStr)]
+pub enum Direction {
+    North,  // -z
+    South,  // +z
+    East,   // +x
+    West    // -x
+}
+
+impl Direction {
+    pub fn opposite(&self) -> Direction {
+        match *self {
+            North   => South,
+            South   => North,
+            East    => West,
+            West    => East
+        }
+    }
+}
+
+// inclusive on every axis
+#[deriving(Clone, Eq, ToStr)]
+pub struct BoundingBox {
+    min_x:  i32,
+    min_y:  i32,
+    min_z:  i32,
+    max_x:  i32,
+    max_y:  i32,
+    max_z:  i32
+}
+
+impl BoundingBox {
+    pub fn new(x: i32, y: i32, z: i32, width: i32, height: i32, depth: i32) -> BoundingBox {
+        BoundingBox {
+            min_x:  x,
+            min_y:  y,
+            min_z:  z,
+            max_x:  x + width - 1,
+            max_y:  y + height - 1,
+            max_z:  z + depth - 1
+        }
+    }
+
+    pub fn contains(&self, x: i32, y: i32, z: i32) -> bool {
+        x >= self.min_x && x <= self.max_x && y >= self.min_y && y <= self.max_y && z >= self.min_z && z <= self.max_z
+    }
+
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.min_x <= other.max_x && self.max_x >= other.min_x
+            && self.min_y <= other.max_y && self.max_y >= other.min_y
+            && self.min_z <= other.max_z && self.max_z >= other.min_z
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox {
+            min_x:  if self.min_x < other.min_x { self.min_x } else { other.min_x },
+            min_y:  if self.min_y < other.min_y { self.min_y } else { other.min_y },
+            min_z:  if self.min_z < other.min_z { self.min_z } else { other.min_z },
+            max_x:  if self.max_x > other.max_x { self.max_x } else { other.max_x },
+            max_y:  if self.max_y > other.max_y { self.max_y } else { other.max_y },
+            max_z:  if self.max_z > other.max_z { self.max_z } else { other.max_z }
+        }
+    }
+
+    // the two blocks high opening in the middle of a wall
+    fn is_door(&self, dir: Direction, x: i32, y: i32, z: i32) -> bool {
+        if y != self.min_y + 1 && y != self.min_y + 2 {
+            return false;
+        }
+        let (cx, cz) = ((self.min_x + self.max_x) / 2, (self.min_z + self.max_z) / 2);
+        match dir {
+            North   => z == self.min_z && x == cx,
+            South   => z == self.max_z && x == cx,
+            East    => x == self.max_x && z == cz,
+            West    => x == self.min_x && z == cz
+        }
+    }
+}
+
+#[deriving(Clone, Eq, ToStr)]
+pub enum StructureKind {
+    Village,
+    Dungeon,
+    Fortress
+}
+
+impl StructureKind {
+    pub fn from_name(name: &str) -> Option<StructureKind> {
+        match name {
+            "village"   => Some(Village),
+            "dungeon"   => Some(Dungeon),
+            "fortress"  => Some(Fortress),
+            _           => None
+        }
+    }
+
+    pub fn get_name(&self) -> ~str {
+        match *self {
+            Village     => ~"village",
+            Dungeon     => ~"dungeon",
+            Fortress    => ~"fortress"
+        }
+    }
+
+    // how many pieces away from the start
+    fn max_depth(&self) -> i32 {
+        match *self {
+            Village     => 4,
+            Dungeon     => 6,
+            Fortress    => 5
+        }
+    }
+
+    // surface structures follow the terrain, piece by piece
+    fn on_surface(&self) -> bool {
+        match *self {
+            Dungeon     => false,
+            _           => true
+        }
+    }
+
+    // the first piece is the start
+    fn pieces(&self) -> ~[PieceTemplate] {
+        let all = ~[North, South, East, West];
+        match *self {
+            Village     => ~[
+                piece("plaza", (9, 5, 9), AIR, SAND, false, all.clone(), true, 1),
+                piece("road_x", (9, 4, 3), AIR, SAND, false, ~[East, West], true, 3),
+                piece("road_z", (3, 4, 9), AIR, SAND, false, ~[North, South], true, 3),
+                PieceTemplate { roof: true, .. piece("house", (7, 5, 7), LOG, DIRT, true, all.clone(), false, 2) }
+            ],
+            Dungeon     => ~[
+                PieceTemplate { roof: true, light: true, .. piece("room", (9, 6, 9), STONE_BRICK, STONE_BRICK, true, all.clone(), true, 2) },
+                PieceTemplate { roof: true, .. piece("corridor_x", (9, 5, 5), STONE_BRICK, STONE_BRICK, true, ~[East, West], true, 3) },
+                PieceTemplate { roof: true, .. piece("corridor_z", (5, 5, 9), STONE_BRICK, STONE_BRICK, true, ~[North, South], true, 3) },
+                PieceTemplate { roof: true, light: true, .. piece("cell", (5, 5, 5), STONE_BRICK, STONE_BRICK, true, all.clone(), false, 1) }
+            ],
+            Fortress    => ~[
+                PieceTemplate { roof: true, light: true, .. piece("hall", (11, 8, 11), HELLROCK, HELLSAND, true, all.clone(), true, 2) },
+                piece("bridge_x", (11, 5, 5), HELLROCK, HELLROCK, true, ~[East, West], true, 3),
+                piece("bridge_z", (5, 5, 11), HELLROCK, HELLROCK, true, ~[North, South], true, 3),
+                PieceTemplate { roof: true, light: true, .. piece("tower", (5, 12, 5), HELLROCK, HELLSAND, true, all.clone(), false, 1) }
+            ]
+        }
+    }
+}
+
+#[deriving(Clone)]
+struct PieceTemplate {
+    name:   ~str,
+    // width, height, depth
+    size:   (i32, i32, i32),
+    walls:  BlockId,
+    floor:  BlockId,
+    // false for the open air pieces, only the floor and the air above
+    walled: bool,
+    roof:   bool,
+    // a lightgem in the middle of the roof
+    light:  bool,
+    exits:  ~[Direction],
+    // whether the pieces snapped on this one get new pieces in turn
+    expand: bool,
+    weight: i32
+}
+
+fn piece(name: &str, size: (i32, i32, i32), walls: BlockId, floor: BlockId, walled: bool,
+    exits: ~[Direction], expand: bool, weight: i32) -> PieceTemplate {
+    PieceTemplate {
+        name:   name.to_owned(),
+        size:   size,
+        walls:  walls,
+        floor:  floor,
+        walled: walled,
+        roof:   false,
+        light:  false,
+        exits:  exits,
+        expand: expand,
+        weight: weight
+    }
+}
+
+#[deriving(Clone)]
+pub struct Piece {
+    name:       ~str,
+    bbox:       BoundingBox,
+    priv template:  uint,
+    // the exits connected to another piece
+    priv doors:     ~[Direction]
+}
+
+pub struct Structure {
+    kind:   StructureKind,
+    // around every piece
+    bbox:   BoundingBox,
+    pieces: ~[Piece]
+}
+
+impl Structure {
+    // the piece containing the block, the structure's bbox is only a hint
+    pub fn get_piece_at<'a>(&'a self, x: i32, y: i32, z: i32) -> Option<&'a Piece> {
+        if !self.bbox.contains(x, y, z) {
+            return None;
+        }
+        self.pieces.iter().find(|p| p.bbox.contains(x, y, z))
+    }
+}
+
+pub struct StructureSet {
+    // (kind, chance for a cell), tried in order
+    priv rules:     ~[(StructureKind, f64)],
+    priv templates: ~[~[PieceTemplate]],
+    priv cache:     RefCell<HashMap<(i32, i32), Option<Rc<Structure>>>>
+}
+
+impl StructureSet {
+    pub fn new(rules: ~[(StructureKind, f64)]) -> StructureSet {
+        StructureSet {
+            templates:  rules.iter().map(|&(kind, _)| kind.pieces()).collect(),
+            rules:      rules,
+            cache:      RefCell::new(HashMap::new())
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn get_structure(&self, generator: &Generator, cell_x: i32, cell_z: i32) -> Option<Rc<Structure>> {
+        let cached = self.cache.with(|c| c.find(&(cell_x, cell_z)).map(|s| s.clone()));
+        match cached {
+            Some(s) => return s,
+            None    => {}
+        }
+        let structure = self.assemble(generator, cell_x, cell_z).map(|s| Rc::new(s));
+        self.cache.with_mut(|c| {
+            if c.len() >= MAX_CACHED_CELLS {
+                c.clear();
+            }
+            c.insert((cell_x, cell_z), structure.clone());
+        });
+        structure
+    }
+
+    pub fn structure_at(&self, generator: &Generator, x: i32, y: i32, z: i32) -> Option<Rc<Structure>> {
+        if self.is_empty() {
+            return None;
+        }
+        match self.get_structure(generator, div_floor(x, STRUCTURE_CELL), div_floor(z, STRUCTURE_CELL)) {
+            Some(s) => if s.borrow().get_piece_at(x, y, z).is_some() { Some(s) } else { None },
+            None    => None
+        }
+    }
+
+    pub fn place(&self, generator: &Generator, chunk: &mut Chunk) -> () {
+        if self.is_empty() {
+            return;
+        }
+        let position = chunk.get_position();
+        let (ox, oz) = (position.origin_x(), position.origin_z());
+        let structure = match self.get_structure(generator, div_floor(ox, STRUCTURE_CELL), div_floor(oz, STRUCTURE_CELL)) {
+            Some(s) => s,
+            None    => return
+        };
+        let structure = structure.borrow();
+        let column = BoundingBox::new(ox, 0, oz, CHUNK_SIZE, CHUNK_HEIGHT, CHUNK_SIZE);
+        if !structure.bbox.intersects(&column) {
+            return;
+        }
+        let kind_index = self.rules.iter().position(|&(k, _)| k == structure.kind).unwrap();
+        let templates = &self.templates[kind_index];
+
+        for piece in structure.pieces.iter() {
+            let b = &piece.bbox;
+            if !b.intersects(&column) {
+                continue;
+            }
+            let template = &templates[piece.template];
+            for x in range(if b.min_x > ox { b.min_x } else { ox }, if b.max_x < ox + CHUNK_SIZE - 1 { b.max_x } else { ox + CHUNK_SIZE - 1 } + 1) {
+                for z in range(if b.min_z > oz { b.min_z } else { oz }, if b.max_z < oz + CHUNK_SIZE - 1 { b.max_z } else { oz + CHUNK_SIZE - 1 } + 1) {
+                    for y in range(if b.min_y > 1 { b.min_y } else { 1 }, if b.max_y < CHUNK_HEIGHT - 1 { b.max_y } else { CHUNK_HEIGHT - 1 } + 1) {
+                        chunk.set(x - ox, y, z - oz, piece_block(template, piece, x, y, z));
+                    }
+                }
+            }
+        }
+    }
+
+    fn assemble(&self, generator: &Generator, cell_x: i32, cell_z: i32) -> Option<Structure> {
+        let mut rng = Prng::new(hash_seed(generator.seed, cell_x, cell_z, STRUCTURES_SALT));
+        let mut chosen = None;
+        for (i, &(_, chance)) in self.rules.iter().enumerate() {
+            if rng.chance(chance) {
+                chosen = Some(i);
+                break;
+            }
+        }
+        let kind_index = match chosen {
+            Some(i) => i,
+            None    => return None
+        };
+        let (kind, _) = self.rules[kind_index];
+        let templates = &self.templates[kind_index];
+
+        let sx = cell_x * STRUCTURE_CELL + MAX_EXTENT + rng.gen_range(0, STRUCTURE_CELL - 2 * MAX_EXTENT);
+        let sz = cell_z * STRUCTURE_CELL + MAX_EXTENT + rng.gen_range(0, STRUCTURE_CELL - 2 * MAX_EXTENT);
+        let (w, h, d) = templates[0].size;
+        let ground = generator.height_at(sx, sz);
+        if kind.on_surface() && ground < generator.get_sea_level() {
+            return None;
+        }
+        let sy = if kind.on_surface() { ground } else { if ground - DUNGEON_DEPTH > 4 { ground - DUNGEON_DEPTH } else { 4 } };
+        let start = BoundingBox::new(sx - w / 2, sy, sz - d / 2, w, h, d);
+        let limits = BoundingBox::new(sx - MAX_EXTENT, 1, sz - MAX_EXTENT, 2 * MAX_EXTENT + 1, CHUNK_HEIGHT - 1, 2 * MAX_EXTENT + 1);
+
+        let mut pieces = ~[Piece { name: templates[0].name.clone(), bbox: start.clone(), template: 0, doors: ~[] }];
+        let mut bbox = start;
+        // (piece, exit, depth), handled breadth first
+        let mut open = templates[0].exits.iter().map(|&e| (0u, e, 1)).collect::<~[(uint, Direction, i32)]>();
+        let mut head = 0u;
+
+        while head < open.len() {
+            let (from, exit, depth) = open[head];
+            head += 1;
+            if depth > kind.max_depth() {
+                continue;
+            }
+            let entry = exit.opposite();
+            let candidates: ~[uint] = range(0, templates.len()).filter(|&i| templates[i].exits.contains(&entry)).collect();
+            let total = candidates.iter().fold(0, |sum, &i| sum + templates[i].weight);
+            if total <= 0 {
+                continue;
+            }
+
+            for _ in range(0, PLACE_ATTEMPTS) {
+                let mut roll = rng.gen_range(0, total);
+                let mut index = candidates[0];
+                for &i in candidates.iter() {
+                    if roll < templates[i].weight {
+                        index = i;
+                        break;
+                    }
+                    roll -= templates[i].weight;
+                }
+
+                let b = match snap(generator, kind, &pieces[from].bbox, exit, templates[index].size) {
+                    Some(b) => b,
+                    None    => continue
+                };
+                if !limits.contains(b.min_x, b.min_y, b.min_z) || !limits.contains(b.max_x, b.max_y, b.max_z)
+                    || pieces.iter().any(|p| p.bbox.intersects(&b)) {
+                    continue;
+                }
+
+                pieces[from].doors.push(exit);
+                let new_index = pieces.len();
+                if templates[index].expand {
+                    for &e in templates[index].exits.iter() {
+                        if e != entry {
+                            open.push((new_index, e, depth + 1));
+                        }
+                    }
+                }
+                bbox = bbox.union(&b);
+                pieces.push(Piece { name: templates[index].name.clone(), bbox: b, template: index, doors: ~[entry] });
+                break;
+            }
+        }
+
+        Some(Structure {
+            kind:   kind,
+            bbox:   bbox,
+            pieces: pieces
+        })
+    }
+}
+
+// the box of a piece of `size` entering through the `exit` of `from`, the
+// two doors facing each other. None if the piece can't stand there
+fn snap(generator: &Generator, kind: StructureKind, from: &BoundingBox, exit: Direction, size: (i32, i32, i32)) -> Option<BoundingBox> {
+    let (w, h, d) = size;
+    let (cx, cz) = ((from.min_x + from.max_x) / 2, (from.min_z + from.max_z) / 2);
+    let (x, z) = match exit {
+        North   => (cx - w / 2, from.min_z - d),
+        South   => (cx - w / 2, from.max_z + 1),
+        East    => (from.max_x + 1, cz - d / 2),
+        West    => (from.min_x - w, cz - d / 2)
+    };
+    // on the parent's floor so the doors line up, a surface piece over the
+    // sea would stand in the water
+    if kind.on_surface() && generator.height_at(x + w / 2, z + d / 2) < generator.get_sea_level() {
+        return None;
+    }
+    Some(BoundingBox::new(x, from.min_y, z, w, h, d))
+}
+
+fn piece_block(template: &PieceTemplate, piece: &Piece, x: i32, y: i32, z: i32) -> BlockId {
+    let b = &piece.bbox;
+    if piece.doors.iter().any(|&d| b.is_door(d, x, y, z)) {
+        return AIR;
+    }
+    if y == b.min_y {
+        return template.floor;
+    }
+    if template.roof && y == b.max_y {
+        let centre = x == (b.min_x + b.max_x) / 2 && z == (b.min_z + b.max_z) / 2;
+        return if template.light && centre { LIGHTGEM } else { template.walls };
+    }
+    if template.walled && (x == b.min_x || x == b.max_x || z == b.min_z || z == b.max_z) {
+        return template.walls;
+    }
+    AIR
+}
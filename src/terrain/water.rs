@@ -0,0 +1,104 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// Oceans and rivers, shaping the raw height before the erosion.
+//  - oceans: where a low frequency "continent" noise goes under
+//    OCEAN_THRESHOLD the ground sinks to OCEAN_DEPTH blocks under the sea
+//  - rivers: along the zero crossings of a river noise, a valley goes down
+//    to a bed under the sea level, so the sea fill floods it. The biomes
+//    say how much of the rivers they keep.
+// The columns under the sea level are flooded by the generator.
+
+use math::prng::hash_seed;
+use noise::{NoiseSource, SimplexNoise};
+use noise::combinators::{Fbm, ScalePoint, lerp, smoothstep};
+
+static CONTINENT_FREQUENCY: f64 = 0.0015;
+static OCEAN_THRESHOLD: f64     = -0.25;
+// continent noise range over which the coast goes down to the ocean floor
+static COAST_WIDTH: f64         = 0.1;
+static OCEAN_DEPTH: f64         = 12.;
+
+static RIVER_FREQUENCY: f64     = 0.003;
+// river noise under which the column is in the water, the banks, the valley
+static RIVER_WIDTH: f64         = 0.025;
+static BANK_WIDTH: f64          = 0.045;
+static VALLEY_WIDTH: f64        = 0.15;
+static RIVER_DEPTH: f64         = 3.;
+
+static WATER_SALT: u64          = 0x7761746572;
+
+pub struct WaterSample {
+    // 0 on land, 1 in the open sea
+    ocean:  f64,
+    // 0 out of the valley, 1 in the river bed
+    river:  f64,
+    // in the river or on its sandy banks
+    bank:   bool
+}
+
+pub struct Hydrology {
+    priv continent: Option<~NoiseSource>,
+    priv river:     Option<~NoiseSource>
+}
+
+impl Hydrology {
+    pub fn new(seed: u64, oceans: bool, rivers: bool) -> Hydrology {
+        Hydrology {
+            continent:  if oceans { Some(flat_noise(hash_seed(seed, 1, 0, WATER_SALT), CONTINENT_FREQUENCY, 4)) } else { None },
+            river:      if rivers { Some(flat_noise(hash_seed(seed, 2, 0, WATER_SALT), RIVER_FREQUENCY, 2)) } else { None }
+        }
+    }
+
+    // `rivers` scales the width of the rivers, from the biomes
+    pub fn sample(&self, x: i32, z: i32, rivers: f64) -> WaterSample {
+        let (fx, fz) = (x as f64, z as f64);
+        let ocean = match self.continent {
+            Some(ref c) => smoothstep((OCEAN_THRESHOLD - c.get(fx, 0f64, fz)) / COAST_WIDTH + 0.5),
+            None        => 0f64
+        };
+        let (river, bank) = match self.river {
+            Some(ref r) if rivers > 0f64 => {
+                let d = r.get(fx, 0f64, fz).abs() / rivers;
+                (1f64 - smoothstep((d - RIVER_WIDTH) / (VALLEY_WIDTH - RIVER_WIDTH)), d < BANK_WIDTH)
+            }
+            _                            => (0f64, false)
+        };
+        WaterSample {
+            ocean:  ocean,
+            river:  river,
+            bank:   bank
+        }
+    }
+
+    pub fn shape(&self, height: f64, sea_level: f64, sample: &WaterSample) -> f64 {
+        let h = lerp(sample.ocean, height, sea_level - OCEAN_DEPTH);
+        // the valley only digs, a river never raises the ocean floor
+        let bed = sea_level - RIVER_DEPTH;
+        if h > bed { lerp(sample.river, h, bed) } else { h }
+    }
+}
+
+fn flat_noise(seed: u64, frequency: f64, octaves: uint) -> ~NoiseSource {
+    let base = ~SimplexNoise::<f64>::with_seed(seed) as ~NoiseSource;
+    let scaled = ~ScalePoint::new(base, frequency, 1f64, frequency) as ~NoiseSource;
+    ~Fbm::new(scaled, octaves, 2f64, 0.5f64) as ~NoiseSource
+}
@@ -39,17 +39,29 @@ pub fn make() -> Rc<RefCell<TextureLoader>> {
     tex_loader.load(~"./assets/oreGold.png", gl::RGBA);
     tex_loader.load(~"./assets/oreIron.png", gl::RGBA);
     tex_loader.load(~"./assets/sponge.png", gl::RGBA);
+    tex_loader.load(~"./assets/lightgem.png", gl::RGBA);
+    tex_loader.load(~"./assets/dirt.png", gl::RGBA);
+    tex_loader.load(~"./assets/grass_top.png", gl::RGBA);
+    tex_loader.load(~"./assets/grass_side.png", gl::RGBA);
+    tex_loader.load(~"./assets/sand.png", gl::RGBA);
+    tex_loader.load(~"./assets/log_top.png", gl::RGBA);
+    tex_loader.load(~"./assets/log_side.png", gl::RGBA);
+    tex_loader.load(~"./assets/leaves.png", gl::RGBA);
+    tex_loader.load(~"./assets/water.png", gl::RGBA);
+    tex_loader.load(~"./assets/lava.png", gl::RGBA);
     Rc::new(RefCell::new(tex_loader))
 }
 
 pub struct TextureLoader {
-    priv textures: ~[GLuint]
+    priv textures:  ~[GLuint],
+    priv paths:     ~[~str]
 }
 
 impl TextureLoader {
     pub fn new() -> TextureLoader {
         TextureLoader {
-            textures: ~[]
+            textures:   ~[],
+            paths:      ~[]
         }
     }
 
@@ -57,12 +69,17 @@ impl TextureLoader {
         texture_path: ~str, 
         color_mod: GLenum) -> () {
 
-        self.textures.push(load_texture(texture_path, color_mod));
+        self.textures.push(load_texture(texture_path.clone(), color_mod));
+        self.paths.push(texture_path);
     }
 
     pub fn get(&self, index: u32) -> GLuint {
         self.textures[index]
     }
+
+    pub fn find(&self, texture_path: &str) -> Option<u32> {
+        self.paths.iter().position(|p| p.as_slice() == texture_path).map(|i| i as u32)
+    }
 }
 
 pub fn load_texture(texture_path: ~str, color_mod: GLenum) -> GLuint {
@@ -49,4 +49,101 @@ impl Timer {
     pub fn get_elapsed_time(&self) -> f64 {
         time::precise_time_s() - self.last
     }
-}
\ No newline at end of file
+}
+
+// fixed rate simulation clock, the frames accumulate their time and the
+// simulation catches up one tick at a time
+pub static DEFAULT_TICK_RATE: f64   = 20f64;
+pub static MIN_TICK_RATE: f64       = 1f64;
+pub static MAX_TICK_RATE: f64       = 240f64;
+// past that the simulation slows down instead of freezing the frames
+static MAX_TICKS_PER_FRAME: uint    = 10;
+
+pub struct TickClock {
+    priv timer:         Timer,
+    // ticks per second
+    priv tick_rate:     f64,
+    // time not yet simulated, in seconds
+    priv accumulator:   f64,
+    priv paused:        bool,
+    priv step:          bool
+}
+
+impl TickClock {
+    pub fn new(tick_rate: f64) -> TickClock {
+        TickClock {
+            timer:          Timer::new(),
+            tick_rate:      tick_rate,
+            accumulator:    0f64,
+            paused:         false,
+            step:           false
+        }
+    }
+
+    pub fn get_tick_rate(&self) -> f64 {
+        self.tick_rate
+    }
+
+    pub fn set_tick_rate(&mut self, tick_rate: f64) -> () {
+        self.tick_rate = if tick_rate < MIN_TICK_RATE {
+            MIN_TICK_RATE
+        } else if tick_rate > MAX_TICK_RATE {
+            MAX_TICK_RATE
+        } else {
+            tick_rate
+        };
+        self.accumulator = 0f64;
+    }
+
+    // in seconds
+    pub fn get_tick_duration(&self) -> f64 {
+        1f64 / self.tick_rate
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) -> () {
+        self.paused = paused;
+        self.accumulator = 0f64;
+    }
+
+    // one tick on the next frame, while paused
+    pub fn step(&mut self) -> () {
+        if self.paused {
+            self.step = true;
+        }
+    }
+
+    // ticks to run for this frame
+    pub fn advance(&mut self) -> uint {
+        let elapsed = self.timer.delta_time();
+        if self.paused {
+            let step = self.step;
+            self.step = false;
+            return if step { 1 } else { 0 };
+        }
+
+        self.accumulator += elapsed;
+        let duration = self.get_tick_duration();
+        let mut ticks = 0u;
+        while self.accumulator >= duration && ticks < MAX_TICKS_PER_FRAME {
+            self.accumulator -= duration;
+            ticks += 1;
+        }
+        if ticks == MAX_TICKS_PER_FRAME {
+            self.accumulator = 0f64;
+        }
+        ticks
+    }
+
+    // how far the frame is between the last tick and the next, in [0, 1]
+    pub fn get_alpha(&self) -> f32 {
+        if self.paused {
+            1f32
+        } else {
+            (self.accumulator / self.get_tick_duration()) as f32
+        }
+    }
+}
@@ -0,0 +1,184 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::rc::Rc;
+
+use texture_loader::TextureLoader;
+
+pub type BlockId = u8;
+
+// ids are the registration order in make()
+pub static AIR: BlockId             = 0;
+pub static STONE_BRICK: BlockId     = 1;
+pub static BEDROCK: BlockId         = 2;
+pub static HELLROCK: BlockId        = 3;
+pub static HELLSAND: BlockId        = 4;
+pub static GOLD_ORE: BlockId        = 5;
+pub static IRON_ORE: BlockId        = 6;
+pub static SPONGE: BlockId          = 7;
+pub static LIGHTGEM: BlockId        = 8;
+pub static DIRT: BlockId            = 9;
+pub static GRASS: BlockId           = 10;
+pub static SAND: BlockId            = 11;
+pub static LOG: BlockId             = 12;
+pub static LEAVES: BlockId          = 13;
+pub static WATER: BlockId           = 14;
+pub static LAVA: BlockId            = 15;
+
+// same order as the faces in cube.rs
+#[deriving(Clone, Eq, ToStr)]
+pub enum Face {
+    Front,
+    Top,
+    Back,
+    Bottom,
+    Left,
+    Right
+}
+
+pub static FACES: [Face, ..6] = [Front, Top, Back, Bottom, Left, Right];
+
+#[deriving(Clone)]
+pub struct BlockDef {
+    name:           ~str,
+    // index in the TextureLoader for each face
+    textures:       [u32, ..6],
+    solid:          bool,
+    transparent:    bool,
+    // 0 - 15
+    light_emission: u8,
+    // negative hardness means the block can't be broken
+    hardness:       f32
+}
+
+impl BlockDef {
+    pub fn get_texture(&self, face: Face) -> u32 {
+        self.textures[face as uint]
+    }
+}
+
+pub struct BlockRegistry {
+    priv blocks: ~[BlockDef]
+}
+
+impl BlockRegistry {
+    pub fn new() -> BlockRegistry {
+        let mut registry = BlockRegistry {
+            blocks: ~[]
+        };
+        registry.register(BlockDef {
+            name:           ~"air",
+            textures:       [0, ..6],
+            solid:          false,
+            transparent:    true,
+            light_emission: 0,
+            hardness:       0f32
+        });
+        registry
+    }
+
+    pub fn register(&mut self, block: BlockDef) -> BlockId {
+        if self.blocks.len() > 255 {
+            fail!("Cannot register block {}: registry is full", block.name);
+        }
+        self.blocks.push(block);
+        (self.blocks.len() - 1) as BlockId
+    }
+
+    pub fn get<'a>(&'a self, id: BlockId) -> &'a BlockDef {
+        &self.blocks[id]
+    }
+
+    pub fn find(&self, name: &str) -> Option<BlockId> {
+        self.blocks.iter().position(|b| b.name.as_slice() == name).map(|i| i as BlockId)
+    }
+
+    pub fn is_solid(&self, id: BlockId) -> bool {
+        self.blocks[id].solid
+    }
+
+    pub fn is_transparent(&self, id: BlockId) -> bool {
+        self.blocks[id].transparent
+    }
+
+    pub fn len(&self) -> uint {
+        self.blocks.len()
+    }
+}
+
+pub fn make(tex_loader: &TextureLoader) -> Rc<BlockRegistry> {
+    let mut registry = BlockRegistry::new();
+    let tex = |name: &str| -> u32 {
+        match tex_loader.find(format!("./assets/{}.png", name).as_slice()) {
+            Some(t) => t,
+            None    => fail!("Missing block texture: {}", name)
+        }
+    };
+
+    registry.register(opaque(~"stone_brick", [tex("stonebrick_cracked"), ..6], 1.5f32));
+    registry.register(opaque(~"bedrock", [tex("bedrock"), ..6], -1f32));
+    registry.register(opaque(~"hellrock", [tex("hellrock"), ..6], 0.4f32));
+    registry.register(opaque(~"hellsand", [tex("hellsand"), ..6], 0.5f32));
+    registry.register(opaque(~"gold_ore", [tex("oreGold"), ..6], 3f32));
+    registry.register(opaque(~"iron_ore", [tex("oreIron"), ..6], 3f32));
+    registry.register(opaque(~"sponge", [tex("sponge"), ..6], 0.6f32));
+    let mut lightgem = opaque(~"lightgem", [tex("lightgem"), ..6], 0.3f32);
+    lightgem.light_emission = 15;
+    registry.register(lightgem);
+    registry.register(opaque(~"dirt", [tex("dirt"), ..6], 0.5f32));
+    let (grass_top, grass_side) = (tex("grass_top"), tex("grass_side"));
+    registry.register(opaque(~"grass",
+        [grass_side, grass_top, grass_side, tex("dirt"), grass_side, grass_side], 0.6f32));
+    registry.register(opaque(~"sand", [tex("sand"), ..6], 0.5f32));
+    let (log_top, log_side) = (tex("log_top"), tex("log_side"));
+    registry.register(opaque(~"log",
+        [log_side, log_top, log_side, log_top, log_side, log_side], 2f32));
+    registry.register(opaque(~"leaves", [tex("leaves"), ..6], 0.2f32));
+    registry.register(fluid(~"water", tex("water")));
+    let mut lava = fluid(~"lava", tex("lava"));
+    lava.light_emission = 15;
+    registry.register(lava);
+
+    Rc::new(registry)
+}
+
+// can't be walked on nor broken
+fn fluid(name: ~str, texture: u32) -> BlockDef {
+    BlockDef {
+        name:           name,
+        textures:       [texture, ..6],
+        solid:          false,
+        transparent:    true,
+        light_emission: 0,
+        hardness:       -1f32
+    }
+}
+
+fn opaque(name: ~str, textures: [u32, ..6], hardness: f32) -> BlockDef {
+    BlockDef {
+        name:           name,
+        textures:       textures,
+        solid:          true,
+        transparent:    false,
+        light_emission: 0,
+        hardness:       hardness
+    }
+}
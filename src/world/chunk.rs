@@ -0,0 +1,241 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::vec;
+
+use terrain::biome::{BiomeId, PLAINS};
+use world::block::{BlockId, AIR};
+
+pub static CHUNK_SIZE: i32      = 16;
+pub static CHUNK_HEIGHT: i32    = 256;
+pub static CHUNK_VOLUME: uint   = 16 * 16 * 256;
+pub static CHUNK_AREA: uint     = 16 * 16;
+pub static MAX_LIGHT: u8        = 15;
+
+// version 1 had no biomes, version 2 no fluid levels
+static CHUNK_FORMAT_VERSION: u8 = 3;
+
+#[deriving(Clone, Eq, IterBytes, ToStr)]
+pub struct ChunkPos {
+    x: i32,
+    z: i32
+}
+
+impl ChunkPos {
+    pub fn new(x: i32, z: i32) -> ChunkPos {
+        ChunkPos {
+            x: x,
+            z: z
+        }
+    }
+
+    // chunk holding the column at world coordinates (x, z)
+    pub fn from_block(x: i32, z: i32) -> ChunkPos {
+        ChunkPos {
+            x: div_floor(x, CHUNK_SIZE),
+            z: div_floor(z, CHUNK_SIZE)
+        }
+    }
+
+    // world coordinates of the chunk's (0, 0, 0) block
+    pub fn origin_x(&self) -> i32 {
+        self.x * CHUNK_SIZE
+    }
+
+    pub fn origin_z(&self) -> i32 {
+        self.z * CHUNK_SIZE
+    }
+}
+
+pub struct Chunk {
+    priv position:  ChunkPos,
+    priv blocks:    ~[BlockId],
+    // one per column
+    priv biomes:    ~[BiomeId],
+    // fluid level of each block, see world::fluids
+    priv levels:    ~[u8],
+    // sky light in the high nibble, block light in the low one. computed
+    // when the chunk is loaded, see world::light, and never saved
+    priv light:     ~[u8],
+    priv modified:  bool
+}
+
+impl Chunk {
+    pub fn new(position: ChunkPos) -> Chunk {
+        Chunk {
+            position:   position,
+            blocks:     vec::from_elem(CHUNK_VOLUME, AIR),
+            biomes:     vec::from_elem(CHUNK_AREA, PLAINS),
+            levels:     vec::from_elem(CHUNK_VOLUME, 0u8),
+            light:      vec::from_elem(CHUNK_VOLUME, 0u8),
+            modified:   false
+        }
+    }
+
+    pub fn get_position(&self) -> ChunkPos {
+        self.position.clone()
+    }
+
+    // local coordinates, x and z in [0, CHUNK_SIZE), y in [0, CHUNK_HEIGHT).
+    // outside of the chunk, air is read and nothing is written
+    pub fn get(&self, x: i32, y: i32, z: i32) -> BlockId {
+        if !in_bounds(x, y, z) {
+            AIR
+        } else {
+            self.blocks[index(x, y, z)]
+        }
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, z: i32, block: BlockId) -> () {
+        if in_bounds(x, y, z) {
+            self.blocks[index(x, y, z)] = block;
+            self.levels[index(x, y, z)] = 0;
+            self.modified = true;
+        }
+    }
+
+    pub fn get_level(&self, x: i32, y: i32, z: i32) -> u8 {
+        if !in_bounds(x, y, z) {
+            0
+        } else {
+            self.levels[index(x, y, z)]
+        }
+    }
+
+    pub fn set_level(&mut self, x: i32, y: i32, z: i32, level: u8) -> () {
+        if in_bounds(x, y, z) {
+            self.levels[index(x, y, z)] = level;
+            self.modified = true;
+        }
+    }
+
+    // full above the chunk, none under it
+    pub fn get_sky_light(&self, x: i32, y: i32, z: i32) -> u8 {
+        if y >= CHUNK_HEIGHT && in_column(x, z) {
+            MAX_LIGHT
+        } else if !in_bounds(x, y, z) {
+            0
+        } else {
+            self.light[index(x, y, z)] >> 4
+        }
+    }
+
+    pub fn set_sky_light(&mut self, x: i32, y: i32, z: i32, light: u8) -> () {
+        if in_bounds(x, y, z) {
+            let i = index(x, y, z);
+            self.light[i] = (self.light[i] & 0x0f) | (light << 4);
+        }
+    }
+
+    pub fn get_block_light(&self, x: i32, y: i32, z: i32) -> u8 {
+        if !in_bounds(x, y, z) {
+            0
+        } else {
+            self.light[index(x, y, z)] & 0x0f
+        }
+    }
+
+    pub fn set_block_light(&mut self, x: i32, y: i32, z: i32, light: u8) -> () {
+        if in_bounds(x, y, z) {
+            let i = index(x, y, z);
+            self.light[i] = (self.light[i] & 0xf0) | (light & 0x0f);
+        }
+    }
+
+    pub fn get_biome(&self, x: i32, z: i32) -> BiomeId {
+        if in_column(x, z) { self.biomes[(z * CHUNK_SIZE + x) as uint] } else { PLAINS }
+    }
+
+    pub fn set_biome(&mut self, x: i32, z: i32, biome: BiomeId) -> () {
+        if in_column(x, z) {
+            self.biomes[(z * CHUNK_SIZE + x) as uint] = biome;
+            self.modified = true;
+        }
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.modified
+    }
+
+    pub fn set_modified(&mut self, modified: bool) -> () {
+        self.modified = modified;
+    }
+
+    // raw payload stored in the region files
+    pub fn encode(&self) -> ~[u8] {
+        let mut bytes = ~[CHUNK_FORMAT_VERSION];
+        bytes.push_all(self.blocks.as_slice());
+        bytes.push_all(self.biomes.as_slice());
+        bytes.push_all(self.levels.as_slice());
+        bytes
+    }
+
+    pub fn decode(position: ChunkPos, bytes: &[u8]) -> Option<~Chunk> {
+        let version = if bytes.len() > 0 { bytes[0] } else { return None };
+        let expected = match version {
+            1   => CHUNK_VOLUME + 1,
+            2   => CHUNK_VOLUME + CHUNK_AREA + 1,
+            3   => 2 * CHUNK_VOLUME + CHUNK_AREA + 1,
+            _   => return None
+        };
+        if bytes.len() != expected {
+            return None;
+        }
+        let biomes_end = CHUNK_VOLUME + CHUNK_AREA + 1;
+        Some(~Chunk {
+            position:   position,
+            blocks:     bytes.slice(1, CHUNK_VOLUME + 1).to_owned(),
+            biomes:     if version >= 2 {
+                bytes.slice(CHUNK_VOLUME + 1, biomes_end).to_owned()
+            } else {
+                vec::from_elem(CHUNK_AREA, PLAINS)
+            },
+            levels:     if version >= 3 {
+                bytes.slice_from(biomes_end).to_owned()
+            } else {
+                vec::from_elem(CHUNK_VOLUME, 0u8)
+            },
+            light:      vec::from_elem(CHUNK_VOLUME, 0u8),
+            modified:   false
+        })
+    }
+}
+
+fn in_column(x: i32, z: i32) -> bool {
+    x >= 0 && x < CHUNK_SIZE && z >= 0 && z < CHUNK_SIZE
+}
+
+fn in_bounds(x: i32, y: i32, z: i32) -> bool {
+    in_column(x, z) && y >= 0 && y < CHUNK_HEIGHT
+}
+
+fn index(x: i32, y: i32, z: i32) -> uint {
+    ((y * CHUNK_SIZE + z) * CHUNK_SIZE + x) as uint
+}
+
+pub fn div_floor(a: i32, b: i32) -> i32 {
+    if a >= 0 { a / b } else { (a - b + 1) / b }
+}
+
+pub fn mod_floor(a: i32, b: i32) -> i32 {
+    let m = a % b;
+    if m < 0 { m + b } else { m }
+}
@@ -0,0 +1,128 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::hashmap::{HashMap, HashMapIterator, HashMapMutIterator};
+
+use world::block::{BlockId, AIR};
+use world::chunk::{Chunk, ChunkPos, CHUNK_SIZE, MAX_LIGHT, mod_floor};
+
+pub struct ChunkMap {
+    priv chunks: HashMap<ChunkPos, ~Chunk>
+}
+
+impl ChunkMap {
+    pub fn new() -> ChunkMap {
+        ChunkMap {
+            chunks: HashMap::new()
+        }
+    }
+
+    pub fn insert(&mut self, chunk: ~Chunk) -> () {
+        self.chunks.insert(chunk.get_position(), chunk);
+    }
+
+    pub fn remove(&mut self, position: &ChunkPos) -> Option<~Chunk> {
+        self.chunks.pop(position)
+    }
+
+    pub fn contains(&self, position: &ChunkPos) -> bool {
+        self.chunks.contains_key(position)
+    }
+
+    pub fn get_chunk<'a>(&'a self, position: &ChunkPos) -> Option<&'a ~Chunk> {
+        self.chunks.find(position)
+    }
+
+    pub fn get_chunk_mut<'a>(&'a mut self, position: &ChunkPos) -> Option<&'a mut ~Chunk> {
+        self.chunks.find_mut(position)
+    }
+
+    pub fn iter<'a>(&'a self) -> HashMapIterator<'a, ChunkPos, ~Chunk> {
+        self.chunks.iter()
+    }
+
+    pub fn mut_iter<'a>(&'a mut self) -> HashMapMutIterator<'a, ChunkPos, ~Chunk> {
+        self.chunks.mut_iter()
+    }
+
+    pub fn len(&self) -> uint {
+        self.chunks.len()
+    }
+
+    // block at world coordinates, air if the chunk is not loaded
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> BlockId {
+        match self.chunks.find(&ChunkPos::from_block(x, z)) {
+            Some(c) => c.get(mod_floor(x, CHUNK_SIZE), y, mod_floor(z, CHUNK_SIZE)),
+            None    => AIR
+        }
+    }
+
+    // returns false if the chunk holding (x, y, z) is not loaded
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockId) -> bool {
+        match self.chunks.find_mut(&ChunkPos::from_block(x, z)) {
+            Some(c) => { c.set(mod_floor(x, CHUNK_SIZE), y, mod_floor(z, CHUNK_SIZE), block); true },
+            None    => false
+        }
+    }
+
+    pub fn get_level(&self, x: i32, y: i32, z: i32) -> u8 {
+        match self.chunks.find(&ChunkPos::from_block(x, z)) {
+            Some(c) => c.get_level(mod_floor(x, CHUNK_SIZE), y, mod_floor(z, CHUNK_SIZE)),
+            None    => 0
+        }
+    }
+
+    pub fn set_level(&mut self, x: i32, y: i32, z: i32, level: u8) -> bool {
+        match self.chunks.find_mut(&ChunkPos::from_block(x, z)) {
+            Some(c) => { c.set_level(mod_floor(x, CHUNK_SIZE), y, mod_floor(z, CHUNK_SIZE), level); true },
+            None    => false
+        }
+    }
+
+    // full sky light in the unloaded chunks, they are not shaded yet
+    pub fn get_sky_light(&self, x: i32, y: i32, z: i32) -> u8 {
+        match self.chunks.find(&ChunkPos::from_block(x, z)) {
+            Some(c) => c.get_sky_light(mod_floor(x, CHUNK_SIZE), y, mod_floor(z, CHUNK_SIZE)),
+            None    => MAX_LIGHT
+        }
+    }
+
+    pub fn set_sky_light(&mut self, x: i32, y: i32, z: i32, light: u8) -> bool {
+        match self.chunks.find_mut(&ChunkPos::from_block(x, z)) {
+            Some(c) => { c.set_sky_light(mod_floor(x, CHUNK_SIZE), y, mod_floor(z, CHUNK_SIZE), light); true },
+            None    => false
+        }
+    }
+
+    pub fn get_block_light(&self, x: i32, y: i32, z: i32) -> u8 {
+        match self.chunks.find(&ChunkPos::from_block(x, z)) {
+            Some(c) => c.get_block_light(mod_floor(x, CHUNK_SIZE), y, mod_floor(z, CHUNK_SIZE)),
+            None    => 0
+        }
+    }
+
+    pub fn set_block_light(&mut self, x: i32, y: i32, z: i32, light: u8) -> bool {
+        match self.chunks.find_mut(&ChunkPos::from_block(x, z)) {
+            Some(c) => { c.set_block_light(mod_floor(x, CHUNK_SIZE), y, mod_floor(z, CHUNK_SIZE), light); true },
+            None    => false
+        }
+    }
+}
@@ -0,0 +1,202 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// Water and lava flow. A fluid block has a level kept in its chunk:
+// SOURCE for a full block, 1 to MAX_LEVEL for the flowing blocks, each
+// step away from the source adds 1 (2 for the lava), and FALLING for the
+// blocks falling from above, which spread like a source.
+// Fluids move on scheduled ticks, every FLOW_DELAY ticks for the water and
+// LAVA_DELAY for the lava. A change schedules the blocks around it.
+
+use std::hashmap::HashSet;
+
+use world::block::{BlockId, AIR, STONE_BRICK, WATER, LAVA, SPONGE};
+use world::chunk_map::ChunkMap;
+use world::ticks::TickScheduler;
+
+pub static SOURCE: u8                   = 0;
+pub static MAX_LEVEL: u8                = 7;
+pub static FALLING: u8                  = 8;
+static FLOW_DELAY: u64                  = 1;
+static LAVA_DELAY: u64                  = 4;
+// in blocks, through the water
+static SPONGE_RADIUS: i32               = 6;
+static SPONGE_MAX_BLOCKS: uint          = 65;
+
+static HORIZONTAL: [(i32, i32), ..4]    = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+static AROUND: [(i32, i32, i32), ..6]   = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+
+pub fn is_fluid(block: BlockId) -> bool {
+    block == WATER || block == LAVA
+}
+
+// ticks between two moves of the fluid
+pub fn flow_delay(block: BlockId) -> u64 {
+    if block == LAVA { LAVA_DELAY } else { FLOW_DELAY }
+}
+
+// sets the block and schedules the blocks around for their next move
+fn set(chunks: &mut ChunkMap, ticks: &mut TickScheduler, x: i32, y: i32, z: i32,
+    block: BlockId, level: u8, now: u64, changed: &mut ~[(i32, i32, i32)]) -> () {
+    if chunks.set_block(x, y, z, block) {
+        chunks.set_level(x, y, z, level);
+        changed.push((x, y, z));
+        ticks.schedule_around(x, y, z, now + flow_delay(block));
+    }
+}
+
+fn set_level(chunks: &mut ChunkMap, ticks: &mut TickScheduler, x: i32, y: i32, z: i32,
+    block: BlockId, level: u8, now: u64, changed: &mut ~[(i32, i32, i32)]) -> () {
+    chunks.set_level(x, y, z, level);
+    changed.push((x, y, z));
+    ticks.schedule_around(x, y, z, now + flow_delay(block));
+}
+
+// scheduled tick of a water or lava block
+pub fn update(chunks: &mut ChunkMap, ticks: &mut TickScheduler, x: i32, y: i32, z: i32,
+    now: u64, changed: &mut ~[(i32, i32, i32)]) -> () {
+    let block = chunks.get_block(x, y, z);
+    if !is_fluid(block) {
+        return;
+    }
+    let other = if block == WATER { LAVA } else { WATER };
+
+    if block == WATER && touches(&*chunks, x, y, z, SPONGE) {
+        set(chunks, ticks, x, y, z, AIR, 0, now, changed);
+        return;
+    }
+    if touches(&*chunks, x, y, z, other) {
+        if block == LAVA {
+            set(chunks, ticks, x, y, z, STONE_BRICK, 0, now, changed);
+            return;
+        }
+        // the lava around hardens on its own update
+        for &(dx, dy, dz) in AROUND.iter() {
+            if chunks.get_block(x + dx, y + dy, z + dz) == LAVA {
+                ticks.schedule(x + dx, y + dy, z + dz, now + 1);
+            }
+        }
+    }
+
+    let step = if block == WATER { 1 } else { 2 };
+    let mut level = chunks.get_level(x, y, z);
+
+    // a flowing block takes its level from its neighbours, it recedes
+    // when they are gone
+    if level != SOURCE {
+        let mut expected = None;
+        if chunks.get_block(x, y + 1, z) == block {
+            expected = Some(FALLING);
+        } else {
+            let mut sources = 0;
+            for &(dx, dz) in HORIZONTAL.iter() {
+                if chunks.get_block(x + dx, y, z + dz) != block {
+                    continue;
+                }
+                let l = match chunks.get_level(x + dx, y, z + dz) {
+                    SOURCE  => { sources += 1; 0 }
+                    FALLING => 0,
+                    l       => l
+                };
+                let closer = match expected {
+                    Some(e) => l + step < e,
+                    None    => true
+                };
+                if l + step <= MAX_LEVEL && closer {
+                    expected = Some(l + step);
+                }
+            }
+            // two water sources side by side on the ground make a new one
+            let below = chunks.get_block(x, y - 1, z);
+            let grounded = below != AIR && (below != WATER || chunks.get_level(x, y - 1, z) == SOURCE);
+            if block == WATER && sources >= 2 && grounded {
+                expected = Some(SOURCE);
+            }
+        }
+        match expected {
+            None                    => {
+                set(chunks, ticks, x, y, z, AIR, 0, now, changed);
+                return;
+            }
+            Some(l) if l != level   => {
+                set_level(chunks, ticks, x, y, z, block, l, now, changed);
+                level = l;
+            }
+            _                       => {}
+        }
+    }
+
+    // falling goes first
+    let below = chunks.get_block(x, y - 1, z);
+    if below == AIR {
+        set(chunks, ticks, x, y - 1, z, block, FALLING, now, changed);
+        return;
+    }
+    if below == block && chunks.get_level(x, y - 1, z) != SOURCE {
+        return;
+    }
+
+    let spread = if level == FALLING { step } else { level + step };
+    if spread > MAX_LEVEL {
+        return;
+    }
+    for &(dx, dz) in HORIZONTAL.iter() {
+        let (nx, nz) = (x + dx, z + dz);
+        let neighbour = chunks.get_block(nx, y, nz);
+        if neighbour == AIR {
+            set(chunks, ticks, nx, y, nz, block, spread, now, changed);
+        } else if neighbour == block {
+            let l = chunks.get_level(nx, y, nz);
+            if l != SOURCE && l != FALLING && l > spread {
+                set_level(chunks, ticks, nx, y, nz, block, spread, now, changed);
+            }
+        }
+    }
+}
+
+// dries the water connected to the sponge
+pub fn absorb(chunks: &mut ChunkMap, ticks: &mut TickScheduler, x: i32, y: i32, z: i32,
+    now: u64, changed: &mut ~[(i32, i32, i32)]) -> () {
+    let mut open = ~[(x, y, z, 0)];
+    let mut seen = HashSet::new();
+    seen.insert((x, y, z));
+    let mut head = 0u;
+    let mut absorbed = 0u;
+
+    while head < open.len() && absorbed < SPONGE_MAX_BLOCKS {
+        let (px, py, pz, distance) = open[head];
+        head += 1;
+        for &(dx, dy, dz) in AROUND.iter() {
+            let n = (px + dx, py + dy, pz + dz);
+            let (nx, ny, nz) = n;
+            if distance >= SPONGE_RADIUS || !seen.insert(n) || chunks.get_block(nx, ny, nz) != WATER {
+                continue;
+            }
+            set(chunks, ticks, nx, ny, nz, AIR, 0, now, changed);
+            absorbed += 1;
+            open.push((nx, ny, nz, distance + 1));
+        }
+    }
+}
+
+fn touches(chunks: &ChunkMap, x: i32, y: i32, z: i32, block: BlockId) -> bool {
+    AROUND.iter().any(|&(dx, dy, dz)| chunks.get_block(x + dx, y + dy, z + dz) == block)
+}
@@ -0,0 +1,137 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// level.dat holds everything but the chunks:
+//
+//   magic "RCLV" | version: u32 | seed: u64
+//   position: 3 x f32 | h_angle: f32 | v_angle: f32 | time: u64
+//   preset name length: u32 | preset name, utf-8 (since version 2)
+//
+// All the values are little endian.
+
+use std::io::File;
+use std::io;
+use std::str;
+
+use math::Vec3;
+use world::region::rename;
+
+static LEVEL_MAGIC: &'static [u8] = bytes!("RCLV");
+static LEVEL_VERSION: u32 = 2;
+// in bytes, a longer name means a damaged file
+static MAX_PRESET_NAME: uint = 256;
+
+#[deriving(Clone)]
+pub struct LevelData {
+    seed:       u64,
+    position:   Vec3<f32>,
+    h_angle:    f32,
+    v_angle:    f32,
+    // world ticks since the creation of the world
+    time:       u64,
+    // generator preset the world was created with
+    preset:     ~str
+}
+
+impl LevelData {
+    // None if the file does not exist. a damaged file is moved aside and
+    // a new world is created
+    pub fn load(path: &Path) -> Option<LevelData> {
+        if !path.exists() {
+            return None;
+        }
+        let mut failed = false;
+        let level = io::io_error::cond.trap(|e| {
+            println!("Cannot read level file {}: {}", path.display(), e.to_str());
+            failed = true;
+        }).inside(|| LevelData::read(path));
+        match level {
+            Some(l) if !failed  => Some(l),
+            _                   => {
+                let aside = path.with_extension("corrupt");
+                println!("Invalid level file: {}, moved to {}", path.display(), aside.display());
+                if !rename(path, &aside) {
+                    fail!("Cannot move the invalid level file {} aside", path.display());
+                }
+                None
+            }
+        }
+    }
+
+    // the reads past the end of the file raise io_error
+    fn read(path: &Path) -> Option<LevelData> {
+        let mut f = match File::open(path) {
+            Some(f) => f,
+            None    => return None
+        };
+        let magic = f.read_bytes(4);
+        let version = f.read_le_u32();
+        if magic.as_slice() != LEVEL_MAGIC || version == 0 || version > LEVEL_VERSION {
+            return None;
+        }
+        let seed = f.read_le_u64();
+        let position = Vec3::new(f.read_le_f32(), f.read_le_f32(), f.read_le_f32());
+        let h_angle = f.read_le_f32();
+        let v_angle = f.read_le_f32();
+        let time = f.read_le_u64();
+        let preset = if version >= 2 {
+            let len = f.read_le_u32() as uint;
+            if len > MAX_PRESET_NAME {
+                return None;
+            }
+            match str::from_utf8_owned_opt(f.read_bytes(len)) {
+                Some(name)  => name,
+                None        => return None
+            }
+        } else {
+            ~"default"
+        };
+        Some(LevelData {
+            seed:       seed,
+            position:   position,
+            h_angle:    h_angle,
+            v_angle:    v_angle,
+            time:       time,
+            preset:     preset
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> () {
+        let tmp = path.with_extension("tmp");
+        match File::create(&tmp) {
+            Some(mut f) => {
+                f.write(LEVEL_MAGIC);
+                f.write_le_u32(LEVEL_VERSION);
+                f.write_le_u64(self.seed);
+                f.write_le_f32(self.position.x);
+                f.write_le_f32(self.position.y);
+                f.write_le_f32(self.position.z);
+                f.write_le_f32(self.h_angle);
+                f.write_le_f32(self.v_angle);
+                f.write_le_u64(self.time);
+                f.write_le_u32(self.preset.len() as u32);
+                f.write(self.preset.as_bytes());
+            }
+            None        => fail!("Cannot write level file: {}", tmp.display())
+        }
+        rename(&tmp, path);
+    }
+}
@@ -0,0 +1,254 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// Sky light and block light, 0 to MAX_LIGHT, flood filled through the
+// transparent blocks. Each step costs one level, except the full sky light
+// which goes straight down without loss. The emissive blocks are the
+// sources of the block light.
+// A loaded chunk is lit from scratch and exchanges its light with the
+// loaded chunks around. A changed block first removes the light it may
+// have carried, then the light around fills the hole again.
+
+use world::block::BlockRegistry;
+use world::chunk::{ChunkPos, CHUNK_SIZE, CHUNK_HEIGHT, MAX_LIGHT};
+use world::chunk_map::ChunkMap;
+
+#[deriving(Clone, Eq, ToStr)]
+pub enum LightChannel {
+    SkyLight,
+    BlockLight
+}
+
+static CHANNELS: [LightChannel, ..2]    = [SkyLight, BlockLight];
+static HORIZONTAL: [(i32, i32), ..4]    = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+static AROUND: [(i32, i32, i32), ..6]   = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+
+pub fn get(chunks: &ChunkMap, channel: LightChannel, x: i32, y: i32, z: i32) -> u8 {
+    match channel {
+        SkyLight    => chunks.get_sky_light(x, y, z),
+        BlockLight  => chunks.get_block_light(x, y, z)
+    }
+}
+
+fn set(chunks: &mut ChunkMap, channel: LightChannel, x: i32, y: i32, z: i32,
+    light: u8, changed: &mut ~[(i32, i32, i32)]) -> bool {
+    let done = match channel {
+        SkyLight    => chunks.set_sky_light(x, y, z, light),
+        BlockLight  => chunks.set_block_light(x, y, z, light)
+    };
+    if done {
+        changed.push((x, y, z));
+    }
+    done
+}
+
+// light of a chunk which was just inserted in `chunks`
+pub fn light_chunk(chunks: &mut ChunkMap, blocks: &BlockRegistry, position: &ChunkPos,
+    changed: &mut ~[(i32, i32, i32)]) -> () {
+    let (ox, oz) = (position.origin_x(), position.origin_z());
+    let mut tops = ~[];
+    let mut sky = ~[];
+    let mut emitted = ~[];
+
+    match chunks.get_chunk_mut(position) {
+        Some(c) => {
+            for z in range(0, CHUNK_SIZE) {
+                for x in range(0, CHUNK_SIZE) {
+                    // the full sky light down to the first opaque block
+                    let mut y = CHUNK_HEIGHT - 1;
+                    while y >= 0 && blocks.is_transparent(c.get(x, y, z)) {
+                        c.set_sky_light(x, y, z, MAX_LIGHT);
+                        y -= 1;
+                    }
+                    tops.push(y + 1);
+                    for y in range(0, CHUNK_HEIGHT) {
+                        let emission = blocks.get(c.get(x, y, z)).light_emission;
+                        if emission > 0 {
+                            c.set_block_light(x, y, z, emission);
+                            emitted.push((ox + x, y, oz + z));
+                        }
+                    }
+                }
+            }
+        }
+        None    => return
+    }
+
+    // the sky light enters the columns shaded by a higher neighbour
+    for z in range(0, CHUNK_SIZE) {
+        for x in range(0, CHUNK_SIZE) {
+            let top = tops[(z * CHUNK_SIZE + x) as uint];
+            for &(dx, dz) in HORIZONTAL.iter() {
+                let (nx, nz) = (x + dx, z + dz);
+                let neighbour_top = if nx >= 0 && nx < CHUNK_SIZE && nz >= 0 && nz < CHUNK_SIZE {
+                    tops[(nz * CHUNK_SIZE + nx) as uint]
+                } else {
+                    match column_top(chunks, blocks, ox + nx, oz + nz) {
+                        Some(t) => t,
+                        None    => continue
+                    }
+                };
+                for y in range(top, neighbour_top) {
+                    sky.push((ox + x, y, oz + z));
+                }
+            }
+        }
+    }
+
+    // and the light of the chunks around comes in
+    for &(dx, dz) in HORIZONTAL.iter() {
+        let neighbour = ChunkPos::new(position.x + dx, position.z + dz);
+        if !chunks.contains(&neighbour) {
+            continue;
+        }
+        for i in range(0, CHUNK_SIZE) {
+            let (x, z) = match (dx, dz) {
+                (1, _)  => (ox + CHUNK_SIZE, oz + i),
+                (-1, _) => (ox - 1, oz + i),
+                (_, 1)  => (ox + i, oz + CHUNK_SIZE),
+                _       => (ox + i, oz - 1)
+            };
+            for y in range(0, CHUNK_HEIGHT) {
+                if chunks.get_sky_light(x, y, z) > 1 {
+                    sky.push((x, y, z));
+                }
+                if chunks.get_block_light(x, y, z) > 1 {
+                    emitted.push((x, y, z));
+                }
+            }
+        }
+    }
+
+    propagate(chunks, blocks, SkyLight, sky, changed);
+    propagate(chunks, blocks, BlockLight, emitted, changed);
+}
+
+// after the block at (x, y, z) changed
+pub fn update(chunks: &mut ChunkMap, blocks: &BlockRegistry, x: i32, y: i32, z: i32,
+    changed: &mut ~[(i32, i32, i32)]) -> () {
+    if y < 0 || y >= CHUNK_HEIGHT || !chunks.contains(&ChunkPos::from_block(x, z)) {
+        return;
+    }
+    for &channel in CHANNELS.iter() {
+        let mut relight = ~[];
+        let old = get(chunks, channel, x, y, z);
+        if old > 0 {
+            set(chunks, channel, x, y, z, 0, changed);
+            unlight(chunks, blocks, channel, ~[(x, y, z, old)], &mut relight, changed);
+        }
+        if channel == BlockLight {
+            let emission = blocks.get(chunks.get_block(x, y, z)).light_emission;
+            if emission > 0 {
+                set(chunks, channel, x, y, z, emission, changed);
+                relight.push((x, y, z));
+            }
+        }
+        for &(dx, dy, dz) in AROUND.iter() {
+            relight.push((x + dx, y + dy, z + dz));
+        }
+        propagate(chunks, blocks, channel, relight, changed);
+    }
+}
+
+// removes the light fed by the `removed` blocks, given with their old
+// level. the brighter blocks met on the way are queued in `relight`
+fn unlight(chunks: &mut ChunkMap, blocks: &BlockRegistry, channel: LightChannel,
+    removed: ~[(i32, i32, i32, u8)], relight: &mut ~[(i32, i32, i32)],
+    changed: &mut ~[(i32, i32, i32)]) -> () {
+    let mut open = removed;
+    let mut head = 0u;
+
+    while head < open.len() {
+        let (x, y, z, light) = open[head];
+        head += 1;
+        for &(dx, dy, dz) in AROUND.iter() {
+            let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+            if ny < 0 || !chunks.contains(&ChunkPos::from_block(nx, nz)) {
+                continue;
+            }
+            let neighbour = get(chunks, channel, nx, ny, nz);
+            if neighbour == 0 {
+                continue;
+            }
+            // above the world, the sky is always there
+            if ny >= CHUNK_HEIGHT {
+                relight.push((nx, ny, nz));
+                continue;
+            }
+            let fed = neighbour < light || (channel == SkyLight && dy == -1 && light == MAX_LIGHT);
+            if !fed {
+                relight.push((nx, ny, nz));
+                continue;
+            }
+            set(chunks, channel, nx, ny, nz, 0, changed);
+            open.push((nx, ny, nz, neighbour));
+            // an emissive block keeps its own light
+            if channel == BlockLight {
+                let emission = blocks.get(chunks.get_block(nx, ny, nz)).light_emission;
+                if emission > 0 {
+                    set(chunks, channel, nx, ny, nz, emission, changed);
+                    relight.push((nx, ny, nz));
+                }
+            }
+        }
+    }
+}
+
+// spreads the light of the `sources` blocks
+fn propagate(chunks: &mut ChunkMap, blocks: &BlockRegistry, channel: LightChannel,
+    sources: ~[(i32, i32, i32)], changed: &mut ~[(i32, i32, i32)]) -> () {
+    let mut open = sources;
+    let mut head = 0u;
+
+    while head < open.len() {
+        let (x, y, z) = open[head];
+        head += 1;
+        if !chunks.contains(&ChunkPos::from_block(x, z)) {
+            continue;
+        }
+        let light = get(chunks, channel, x, y, z);
+        if light <= 1 {
+            continue;
+        }
+        for &(dx, dy, dz) in AROUND.iter() {
+            let (nx, ny, nz) = (x + dx, y + dy, z + dz);
+            if ny < 0 || ny >= CHUNK_HEIGHT || !blocks.is_transparent(chunks.get_block(nx, ny, nz)) {
+                continue;
+            }
+            let next = if channel == SkyLight && dy == -1 && light == MAX_LIGHT { MAX_LIGHT } else { light - 1 };
+            if get(chunks, channel, nx, ny, nz) < next && set(chunks, channel, nx, ny, nz, next, changed) {
+                open.push((nx, ny, nz));
+            }
+        }
+    }
+}
+
+// first block over the last opaque block of a loaded column
+fn column_top(chunks: &ChunkMap, blocks: &BlockRegistry, x: i32, z: i32) -> Option<i32> {
+    if !chunks.contains(&ChunkPos::from_block(x, z)) {
+        return None;
+    }
+    let mut y = CHUNK_HEIGHT - 1;
+    while y >= 0 && blocks.is_transparent(chunks.get_block(x, y, z)) {
+        y -= 1;
+    }
+    Some(y + 1)
+}
@@ -0,0 +1,196 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::hashmap::HashMap;
+use std::vec;
+
+use world::block::{BlockRegistry, AIR, Face, Front, Top, Back, Bottom, Left, Right};
+use world::chunk::{ChunkPos, CHUNK_SIZE, CHUNK_HEIGHT, MAX_LIGHT};
+use world::chunk_map::ChunkMap;
+
+#[deriving(Clone)]
+pub struct Vertex {
+    x: f32,
+    y: f32,
+    z: f32,
+    u: f32,
+    v: f32,
+    // in [0, 1], the light in front of the face
+    sky_light: f32,
+    block_light: f32
+}
+
+// all the triangles of a chunk using the same texture
+pub struct MeshBatch {
+    texture:    u32,
+    vertices:   ~[Vertex]
+}
+
+pub struct MeshData {
+    batches: ~[MeshBatch]
+}
+
+impl MeshData {
+    pub fn vertices_count(&self) -> uint {
+        self.batches.iter().fold(0, |acc, b| acc + b.vertices.len())
+    }
+}
+
+static DIMS: [i32, ..3] = [CHUNK_SIZE, CHUNK_HEIGHT, CHUNK_SIZE];
+
+// (axis, direction along the axis, block face)
+static DIRECTIONS: [(uint, i32, Face), ..6] = [
+    (0, 1, Right), (0, -1, Left),
+    (1, 1, Top), (1, -1, Bottom),
+    (2, 1, Front), (2, -1, Back)
+];
+
+// Build the visible faces of a chunk. Faces touching an opaque block are
+// culled, including across chunk borders when the neighbour is loaded.
+// In greedy mode coplanar faces sharing a texture and a light are merged in
+// rectangles.
+pub fn build(chunks: &ChunkMap,
+    blocks: &BlockRegistry,
+    position: &ChunkPos,
+    greedy: bool) -> MeshData {
+
+    let mut batches: HashMap<u32, ~[Vertex]> = HashMap::new();
+    let chunk = match chunks.get_chunk(position) {
+        Some(c) => c,
+        None    => return MeshData { batches: ~[] }
+    };
+    let (ox, oz) = (position.origin_x(), position.origin_z());
+
+    for &(d, dir, face) in DIRECTIONS.iter() {
+        let u = (d + 1) % 3;
+        let v = (d + 2) % 3;
+        let (w, h) = (DIMS[u], DIMS[v]);
+        // (texture, sky light << 4 | block light) of each visible face
+        let mut mask: ~[Option<(u32, u8)>] = vec::from_elem((w * h) as uint, None);
+
+        for i in range(0, DIMS[d]) {
+            // faces of slice i looking toward dir
+            for b in range(0, h) {
+                for a in range(0, w) {
+                    let mut p = [0i32, ..3];
+                    p[d] = i; p[u] = a; p[v] = b;
+                    let block = chunk.get(p[0], p[1], p[2]);
+                    let mut face_key = None;
+                    if block != AIR {
+                        p[d] += dir;
+                        let outside = p[0] < 0 || p[0] >= CHUNK_SIZE || p[2] < 0 || p[2] >= CHUNK_SIZE;
+                        let neighbour = if outside {
+                            chunks.get_block(ox + p[0], p[1], oz + p[2])
+                        } else {
+                            chunk.get(p[0], p[1], p[2])
+                        };
+                        if neighbour != block && blocks.is_transparent(neighbour) {
+                            let (sky, emitted) = if outside {
+                                (chunks.get_sky_light(ox + p[0], p[1], oz + p[2]),
+                                 chunks.get_block_light(ox + p[0], p[1], oz + p[2]))
+                            } else {
+                                (chunk.get_sky_light(p[0], p[1], p[2]), chunk.get_block_light(p[0], p[1], p[2]))
+                            };
+                            face_key = Some((blocks.get(block).get_texture(face), (sky << 4) | emitted));
+                        }
+                    }
+                    mask[(a + b * w) as uint] = face_key;
+                }
+            }
+
+            // merge the mask in rectangles
+            for b in range(0, h) {
+                let mut a = 0;
+                while a < w {
+                    let key = mask[(a + b * w) as uint];
+                    if key.is_none() {
+                        a += 1;
+                        continue;
+                    }
+                    let mut rw = 1;
+                    let mut rh = 1;
+                    if greedy {
+                        while a + rw < w && mask[(a + rw + b * w) as uint] == key {
+                            rw += 1;
+                        }
+                        'grow: while b + rh < h {
+                            for k in range(0, rw) {
+                                if mask[(a + k + (b + rh) * w) as uint] != key {
+                                    break 'grow;
+                                }
+                            }
+                            rh += 1;
+                        }
+                    }
+                    for y in range(b, b + rh) {
+                        for x in range(a, a + rw) {
+                            mask[(x + y * w) as uint] = None;
+                        }
+                    }
+
+                    let plane = if dir > 0 { i + 1 } else { i };
+                    let (texture, light) = key.unwrap();
+                    let quad = make_quad(d, u, v, dir, plane, a, b, rw, rh, ox, oz, light);
+                    batches.find_or_insert_with(texture, |_| ~[]).push_all_move(quad);
+                    a += rw;
+                }
+            }
+        }
+    }
+
+    MeshData {
+        batches: batches.move_iter().map(|(t, vertices)| MeshBatch { texture: t, vertices: vertices }).collect()
+    }
+}
+
+// two triangles covering [a, a + w] x [b, b + h] on the plane d = plane,
+// `light` packed like in the mask
+fn make_quad(d: uint, u: uint, v: uint,
+    dir: i32, plane: i32,
+    a: i32, b: i32, w: i32, h: i32,
+    ox: i32, oz: i32, light: u8) -> ~[Vertex] {
+
+    let corners = [(a, b), (a + w, b), (a + w, b + h), (a, b + h)];
+    let mut points: ~[Vertex] = ~[];
+    for &(cu, cv) in corners.iter() {
+        let mut p = [0i32, ..3];
+        p[d] = plane; p[u] = cu; p[v] = cv;
+        let (x, y, z) = (p[0] as f32, p[1] as f32, p[2] as f32);
+        // textures repeat once per block, vertical on the sides. images are
+        // uploaded top row first, so t goes down the side faces
+        let (s, t) = match d {
+            0 => (z, -y),
+            1 => (x, z),
+            _ => (x, -y)
+        };
+        points.push(Vertex {
+            x: (ox + p[0]) as f32,
+            y: y,
+            z: (oz + p[2]) as f32,
+            u: s,
+            v: t,
+            sky_light: (light >> 4) as f32 / MAX_LIGHT as f32,
+            block_light: (light & 0x0f) as f32 / MAX_LIGHT as f32
+        });
+    }
+    let order = if dir > 0 { [0u, 1, 2, 2, 3, 0] } else { [0u, 3, 2, 2, 1, 0] };
+    order.iter().map(|&i| points[i].clone()).collect()
+}
@@ -0,0 +1,493 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::hashmap::HashSet;
+
+use extra::time;
+use glfw;
+
+use math::{Aabb, Vec2, Vec3, Prng};
+use chunk_renderer::ChunkRenderer;
+use texture_loader::TextureLoader;
+use camera::Camera;
+use player::{Player, Noclip, Collision, Passable, Solid, Liquid, SPRINT_FOV_SCALE};
+use input_manager::InputDatas;
+use terrain::Generator;
+use terrain::biome::{Biome, BiomeId};
+use terrain::structures::Structure;
+use terrain::preset::Preset;
+use world::block::{BlockRegistry, BlockId, AIR, STONE_BRICK, DIRT, GRASS, SAND, LOG, LEAVES, LIGHTGEM, SPONGE, WATER};
+use world::chunk::{ChunkPos, CHUNK_SIZE, mod_floor};
+use world::chunk_map::ChunkMap;
+use world::ticks::{TickScheduler, MAX_SCHEDULED_PER_TICK, DEFAULT_RANDOM_TICKS};
+use world::level::LevelData;
+use world::raycast::{RayHit, DEFAULT_REACH};
+use world::region::RegionStore;
+use world::streaming::{ChunkStreamer, DEFAULT_RENDER_DISTANCE, CHUNKS_PER_UPDATE};
+
+pub mod block;
+pub mod chunk;
+pub mod chunk_map;
+pub mod fluids;
+pub mod level;
+pub mod light;
+pub mod mesher;
+pub mod raycast;
+pub mod region;
+pub mod streaming;
+pub mod ticks;
+
+static AUTOSAVE_TICKS: u64 = 1200;
+
+// blocks picked with the number keys, 1 to 9
+static PALETTE: [BlockId, ..9] = [STONE_BRICK, DIRT, GRASS, SAND, LOG, LEAVES, LIGHTGEM, SPONGE, WATER];
+
+
+pub struct World {
+    priv chunks:            ChunkMap,
+    priv generator:         Generator,
+    priv streamer:          ChunkStreamer,
+    priv regions:           RegionStore,
+    priv save_path:         Path,
+    // world ticks since the creation of the world
+    priv time:              u64,
+    // priv position:          Vec3<f32>,
+    priv camera:            Camera,
+    priv player:            Player,
+    priv renderer:          ChunkRenderer,
+    // chunks whose mesh must be rebuilt before the next draw
+    priv dirty:             HashSet<ChunkPos>,
+    priv ticks:             TickScheduler,
+    // random ticks per chunk and per tick
+    priv random_ticks:      uint,
+    priv rng:               Prng,
+    priv greedy_meshing:    bool,
+    // index in PALETTE of the block placed by the right button
+    priv selected:          uint,
+    priv texture_loader:    Rc<RefCell<TextureLoader>>,
+    priv blocks:            Rc<BlockRegistry>
+}
+
+impl World {
+    pub fn new(texture_loader: Rc<RefCell<TextureLoader>>,
+        blocks: Rc<BlockRegistry>,
+        window_size: Vec2<f32>,
+        save_path: Path,
+        preset_name: &str) -> World {
+
+        // an existing save keeps its seed and preset
+        let level = LevelData::load(&save_path.join("level.dat"));
+        let (seed, preset_name) = match level {
+            Some(ref l) => (l.seed, l.preset.clone()),
+            None        => (time::precise_time_ns(), preset_name.to_owned())
+        };
+        let generator = match Preset::load(preset_name.as_slice()).and_then(|p| Generator::new(seed, &p, blocks.borrow())) {
+            Ok(g)   => g,
+            Err(e)  => fail!("Cannot create the world generator: {}", e)
+        };
+        let mut world = World {
+            chunks:             ChunkMap::new(),
+            generator:          generator,
+            streamer:           ChunkStreamer::new(DEFAULT_RENDER_DISTANCE),
+            regions:            RegionStore::new(save_path.join("region")),
+            save_path:          save_path,
+            time:               0,
+            // position:           Vec3::new(0f32, 0f32, 0f32),
+            camera:             Camera::new(window_size),
+            player:             Player::new(Vec3::new(0.5f64, 0f64, 0.5f64)),
+            renderer:           ChunkRenderer::new(),
+            dirty:              HashSet::new(),
+            ticks:              TickScheduler::new(),
+            random_ticks:       DEFAULT_RANDOM_TICKS,
+            rng:                Prng::new(seed),
+            greedy_meshing:     true,
+            selected:           0,
+            texture_loader:     texture_loader,
+            blocks:             blocks
+        };
+        match level {
+            Some(l) => {
+                world.time = l.time;
+                world.player.set_eye_position(&l.position);
+                world.camera.set_angles(l.h_angle, l.v_angle);
+            }
+            // new world, start on the ground
+            None    => {
+                let y = world.generator.height_at(0, 0) + 1;
+                world.player.set_position(&Vec3::new(0.5f64, y as f64, 0.5f64));
+            }
+        }
+        world.camera.set_position(&world.player.get_eye_position());
+        world.set_render_distance(DEFAULT_RENDER_DISTANCE);
+        world
+    }
+
+    // one fixed step of the simulation, `delta_time` is the tick duration
+    pub fn tick(&mut self, 
+        input_datas: &InputDatas,
+        delta_time: f32) -> () {
+
+        self.tick_player(input_datas, delta_time);
+        self.stream_chunks();
+
+        self.time += 1;
+        self.tick_blocks();
+
+        if self.time % AUTOSAVE_TICKS == 0 {
+            self.save();
+        }
+    }
+
+    // every frame, `alpha` is how far the frame is between the last two ticks
+    pub fn update(&mut self, 
+        input_datas: &InputDatas,
+        frame_time: f64,
+        alpha: f32) -> () {
+
+        self.player.handle_inputs(input_datas);
+        self.camera.update(input_datas, frame_time, alpha);
+        self.handle_edit_inputs(input_datas);
+        // right after the edits, so they show on this frame
+        self.rebuild_meshes();
+    }
+
+    // left button breaks, right button places, the number keys select
+    fn handle_edit_inputs(&mut self, input_datas: &InputDatas) -> () {
+        for k in input_datas.keys.iter() {
+            match k {
+                &(glfw::Press, glfw::Key1) => self.selected = 0,
+                &(glfw::Press, glfw::Key2) => self.selected = 1,
+                &(glfw::Press, glfw::Key3) => self.selected = 2,
+                &(glfw::Press, glfw::Key4) => self.selected = 3,
+                &(glfw::Press, glfw::Key5) => self.selected = 4,
+                &(glfw::Press, glfw::Key6) => self.selected = 5,
+                &(glfw::Press, glfw::Key7) => self.selected = 6,
+                &(glfw::Press, glfw::Key8) => self.selected = 7,
+                &(glfw::Press, glfw::Key9) => self.selected = 8,
+                _                           => {}
+            }
+        }
+        for b in input_datas.mouse_buttons.iter() {
+            match b {
+                &(glfw::Press, glfw::MouseButtonLeft)   => { self.break_block(DEFAULT_REACH); }
+                &(glfw::Press, glfw::MouseButtonRight)  => { self.place_block(PALETTE[self.selected], DEFAULT_REACH); }
+                _                                       => {}
+            }
+        }
+    }
+
+    pub fn get_player<'a>(&'a self) -> &'a Player {
+        &self.player
+    }
+
+    pub fn get_player_mut<'a>(&'a mut self) -> &'a mut Player {
+        &mut self.player
+    }
+
+    pub fn get_selected_block(&self) -> BlockId {
+        PALETTE[self.selected]
+    }
+
+    // the targeted block, unless it is unbreakable
+    pub fn break_block(&mut self, reach: f32) -> bool {
+        let hit = match self.pick_block(reach) {
+            Some(h) => h,
+            None    => return false
+        };
+        if self.blocks.borrow().get(self.get_block(hit.x, hit.y, hit.z)).hardness < 0f32 {
+            return false;
+        }
+        self.set_block(hit.x, hit.y, hit.z, AIR)
+    }
+
+    // against the face of the targeted block, if there is room
+    pub fn place_block(&mut self, block: BlockId, reach: f32) -> bool {
+        let hit = match self.pick_block(reach) {
+            Some(h) => h,
+            None    => return false
+        };
+        let (x, y, z) = hit.get_adjacent();
+        let current = self.get_block(x, y, z);
+        if (x, y, z) == (hit.x, hit.y, hit.z) || (current != AIR && !fluids::is_fluid(current)) {
+            return false;
+        }
+        if self.blocks.borrow().is_solid(block) && self.overlaps_player(x, y, z) {
+            return false;
+        }
+        self.set_block(x, y, z, block)
+    }
+
+    fn tick_player(&mut self, input_datas: &InputDatas, delta_time: f32) -> () {
+        let p = self.player.get_position();
+        let (x, z) = (p.x.floor() as i32, p.z.floor() as i32);
+        // wait for the ground to be loaded before falling on it
+        if self.chunks.contains(&ChunkPos::from_block(x, z)) || self.player.get_mode() == Noclip {
+            let angles = self.camera.get_angles();
+            let chunks = &self.chunks;
+            let blocks = self.blocks.borrow();
+            self.player.tick(input_datas, angles, delta_time, |x, y, z| collision_at(chunks, blocks, x, y, z));
+        }
+        self.camera.move_to(&self.player.get_eye_position());
+        self.camera.set_fov_scale(if self.player.is_sprinting() { SPRINT_FOV_SCALE } else { 1f32 });
+    }
+
+    fn overlaps_player(&self, x: i32, y: i32, z: i32) -> bool {
+        self.player.get_aabb().intersects(&Aabb::block(x, y, z))
+    }
+
+    // write the level data and every modified chunk
+    pub fn save(&mut self) -> () {
+        for (_, c) in self.chunks.mut_iter() {
+            if c.is_modified() {
+                self.regions.save_chunk(&**c);
+                c.set_modified(false);
+            }
+        }
+        self.regions.flush();
+        self.regions.release_unused(&self.chunks);
+
+        let (h_angle, v_angle) = self.camera.get_angles();
+        let level = LevelData {
+            seed:       self.generator.get_seed(),
+            position:   self.player.get_standing_eye_position(),
+            h_angle:    h_angle,
+            v_angle:    v_angle,
+            time:       self.time,
+            preset:     self.generator.get_preset_name()
+        };
+        level.save(&self.save_path.join("level.dat"));
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.generator.get_seed()
+    }
+
+    pub fn get_time(&self) -> u64 {
+        self.time
+    }
+
+    pub fn get_render_distance(&self) -> i32 {
+        self.streamer.get_render_distance()
+    }
+
+    // in chunks
+    pub fn set_render_distance(&mut self, render_distance: i32) -> () {
+        self.streamer.set_render_distance(render_distance);
+        self.camera.set_z_far(((render_distance + 1) * CHUNK_SIZE) as f32);
+    }
+
+    // stored in the chunk when it is loaded, computed otherwise
+    pub fn get_biome(&self, x: i32, z: i32) -> BiomeId {
+        match self.chunks.get_chunk(&ChunkPos::from_block(x, z)) {
+            Some(c) => c.get_biome(mod_floor(x, CHUNK_SIZE), mod_floor(z, CHUNK_SIZE)),
+            None    => self.generator.biome_at(x, z)
+        }
+    }
+
+    pub fn get_biome_def<'a>(&'a self, id: BiomeId) -> Option<&'a Biome> {
+        self.generator.get_biomes().map(|b| b.get(id))
+    }
+
+    // e.g. to know if the player is inside a dungeon
+    pub fn get_structure_at(&self, x: i32, y: i32, z: i32) -> Option<Rc<Structure>> {
+        self.generator.structure_at(x, y, z)
+    }
+
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> BlockId {
+        self.chunks.get_block(x, y, z)
+    }
+
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, block: BlockId) -> bool {
+        if !self.chunks.set_block(x, y, z, block) {
+            return false;
+        }
+        // the blocks around may react, e.g. the fluids flow in
+        self.ticks.schedule_around(x, y, z, self.time + 1);
+        self.mark_block_dirty(x, z);
+        let mut lit = ~[];
+        light::update(&mut self.chunks, self.blocks.borrow(), x, y, z, &mut lit);
+        self.mark_blocks_dirty(lit);
+        true
+    }
+
+    // the block the camera looks at, the fluids and the air are crossed
+    pub fn pick_block(&self, reach: f32) -> Option<RayHit> {
+        let chunks = &self.chunks;
+        raycast::raycast(&self.camera.get_position(), &self.camera.get_direction(), reach, |x, y, z| {
+            let block = chunks.get_block(x, y, z);
+            block != AIR && !fluids::is_fluid(block)
+        })
+    }
+
+    pub fn schedule_tick(&mut self, x: i32, y: i32, z: i32, delay: u64) -> () {
+        self.ticks.schedule(x, y, z, self.time + delay);
+    }
+
+    pub fn get_random_ticks(&self) -> uint {
+        self.random_ticks
+    }
+
+    // per chunk and per tick, 0 stops the grass from spreading
+    pub fn set_random_ticks(&mut self, random_ticks: uint) -> () {
+        self.random_ticks = random_ticks;
+    }
+
+    pub fn get_fluid_level(&self, x: i32, y: i32, z: i32) -> u8 {
+        self.chunks.get_level(x, y, z)
+    }
+
+    pub fn get_sky_light(&self, x: i32, y: i32, z: i32) -> u8 {
+        self.chunks.get_sky_light(x, y, z)
+    }
+
+    pub fn get_block_light(&self, x: i32, y: i32, z: i32) -> u8 {
+        self.chunks.get_block_light(x, y, z)
+    }
+
+    // faces of the neighbour chunks may be uncovered too
+    fn mark_block_dirty(&mut self, x: i32, z: i32) -> () {
+        let (lx, lz) = (mod_floor(x, CHUNK_SIZE), mod_floor(z, CHUNK_SIZE));
+        self.mark_dirty(ChunkPos::from_block(x, z));
+        if lx == 0 { self.mark_dirty(ChunkPos::from_block(x - 1, z)); }
+        if lx == CHUNK_SIZE - 1 { self.mark_dirty(ChunkPos::from_block(x + 1, z)); }
+        if lz == 0 { self.mark_dirty(ChunkPos::from_block(x, z - 1)); }
+        if lz == CHUNK_SIZE - 1 { self.mark_dirty(ChunkPos::from_block(x, z + 1)); }
+    }
+
+    // each column once, the light changes come by thousands
+    fn mark_blocks_dirty(&mut self, positions: ~[(i32, i32, i32)]) -> () {
+        let mut seen = HashSet::new();
+        for &(x, _, z) in positions.iter() {
+            if seen.insert((x, z)) {
+                self.mark_block_dirty(x, z);
+            }
+        }
+    }
+
+    pub fn set_greedy_meshing(&mut self, greedy: bool) -> () {
+        self.greedy_meshing = greedy;
+        for (pos, _) in self.chunks.iter() {
+            self.dirty.insert(pos.clone());
+        }
+    }
+
+    fn mark_dirty(&mut self, position: ChunkPos) -> () {
+        if self.chunks.contains(&position) {
+            self.dirty.insert(position);
+        }
+    }
+
+    fn stream_chunks(&mut self) -> () {
+        let unload = self.streamer.update(&self.chunks, 
+            &self.camera.get_position(), 
+            &self.camera.get_direction());
+        for pos in unload.iter() {
+            self.unload_chunk(pos);
+        }
+
+        for _ in range(0, CHUNKS_PER_UPDATE) {
+            match self.streamer.next() {
+                Some(pos)   => self.load_chunk(pos),
+                None        => break
+            }
+        }
+    }
+
+    fn load_chunk(&mut self, position: ChunkPos) -> () {
+        if self.chunks.contains(&position) {
+            return;
+        }
+        let chunk = match self.regions.load_chunk(&position) {
+            Some(c) => c,
+            None    => self.generator.generate(&position)
+        };
+        self.chunks.insert(chunk);
+        ticks::schedule_chunk(&self.chunks, &mut self.ticks, &position, self.time + 1);
+        let mut lit = ~[];
+        light::light_chunk(&mut self.chunks, self.blocks.borrow(), &position, &mut lit);
+        self.mark_blocks_dirty(lit);
+        // neighbours may have faces hidden by the new chunk
+        self.mark_dirty(ChunkPos::new(position.x - 1, position.z));
+        self.mark_dirty(ChunkPos::new(position.x + 1, position.z));
+        self.mark_dirty(ChunkPos::new(position.x, position.z - 1));
+        self.mark_dirty(ChunkPos::new(position.x, position.z + 1));
+        self.mark_dirty(position);
+    }
+
+    fn unload_chunk(&mut self, position: &ChunkPos) -> () {
+        self.renderer.remove(position);
+        self.dirty.remove(position);
+        match self.chunks.remove(position) {
+            Some(c) => if c.is_modified() { self.regions.save_chunk(&*c); },
+            None    => {}
+        }
+    }
+
+    fn tick_blocks(&mut self) -> () {
+        let mut changed = ~[];
+        for _ in range(0, MAX_SCHEDULED_PER_TICK) {
+            match self.ticks.pop_due(self.time) {
+                Some((x, y, z)) => ticks::scheduled_tick(&mut self.chunks, &mut self.ticks, x, y, z, self.time, &mut changed),
+                None            => break
+            }
+        }
+        let positions = ticks::random_positions(&self.chunks, &mut self.rng, self.random_ticks);
+        for &(x, y, z) in positions.iter() {
+            ticks::random_tick(&mut self.chunks, self.blocks.borrow(), &mut self.rng, x, y, z, &mut changed);
+        }
+        let mut lit = ~[];
+        for &(x, y, z) in changed.iter() {
+            light::update(&mut self.chunks, self.blocks.borrow(), x, y, z, &mut lit);
+        }
+        changed.push_all_move(lit);
+        self.mark_blocks_dirty(changed);
+    }
+
+    fn rebuild_meshes(&mut self) -> () {
+        let dirty = ::std::mem::replace(&mut self.dirty, HashSet::new());
+        for pos in dirty.iter() {
+            let mesh = mesher::build(&self.chunks, self.blocks.borrow(), pos, self.greedy_meshing);
+            self.renderer.upload(pos, &mesh);
+        }
+    }
+
+    pub fn draw(&mut self) -> () {
+        let cam = self.camera.get_mat();
+        self.texture_loader.borrow().with(|loader| self.renderer.draw(&cam, loader));
+    }
+}
+
+// the unloaded chunks are walls until they are streamed in
+fn collision_at(chunks: &ChunkMap, blocks: &BlockRegistry, x: i32, y: i32, z: i32) -> Collision {
+    if y < 0 || !chunks.contains(&ChunkPos::from_block(x, z)) {
+        return Solid;
+    }
+    let block = chunks.get_block(x, y, z);
+    if blocks.is_solid(block) {
+        Solid
+    } else if fluids::is_fluid(block) {
+        Liquid
+    } else {
+        Passable
+    }
+}
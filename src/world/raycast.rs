@@ -0,0 +1,186 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// Grid traversal raycast (Amanatides & Woo), visits every block crossed by
+// the ray in order. Blocks occupy [p, p + 1] on each axis. It knows nothing
+// about the world storage, `solid` says whether the ray stops in a block.
+
+use math::Vec3;
+
+pub static DEFAULT_REACH: f32 = 6f32;
+
+#[deriving(Clone, Eq, ToStr)]
+pub struct RayHit {
+    // block hit
+    x:          i32,
+    y:          i32,
+    z:          i32,
+    // face entered, (0, 0, 0) if the ray starts inside the block
+    normal:     (i32, i32, i32),
+    // from the origin, along the normalized direction
+    distance:   f32
+}
+
+impl RayHit {
+    // where a block placed against the hit face goes
+    pub fn get_adjacent(&self) -> (i32, i32, i32) {
+        let (nx, ny, nz) = self.normal;
+        (self.x + nx, self.y + ny, self.z + nz)
+    }
+}
+
+pub fn raycast(origin: &Vec3<f32>, direction: &Vec3<f32>, max_distance: f32,
+    solid: |i32, i32, i32| -> bool) -> Option<RayHit> {
+
+    let (ox, oy, oz) = (origin.x as f64, origin.y as f64, origin.z as f64);
+    let (mut dx, mut dy, mut dz) = (direction.x as f64, direction.y as f64, direction.z as f64);
+    let len = (dx * dx + dy * dy + dz * dz).sqrt();
+    if len == 0f64 {
+        return None;
+    }
+    dx /= len;
+    dy /= len;
+    dz /= len;
+
+    let mut block = [ox.floor() as i32, oy.floor() as i32, oz.floor() as i32];
+    let dir = [dx, dy, dz];
+    let pos = [ox, oy, oz];
+    let mut step = [0i32, ..3];
+    // distance along the ray to the next boundary on each axis, and between
+    // two boundaries
+    let mut next = [0f64, ..3];
+    let mut delta = [0f64, ..3];
+    for a in range(0u, 3) {
+        if dir[a] > 0f64 {
+            step[a] = 1;
+            delta[a] = 1f64 / dir[a];
+            next[a] = ((block[a] + 1) as f64 - pos[a]) * delta[a];
+        } else if dir[a] < 0f64 {
+            step[a] = -1;
+            delta[a] = -1f64 / dir[a];
+            next[a] = (pos[a] - block[a] as f64) * delta[a];
+        } else {
+            delta[a] = Float::infinity();
+            next[a] = Float::infinity();
+        }
+    }
+
+    let mut distance = 0f64;
+    let mut normal = (0i32, 0i32, 0i32);
+    loop {
+        if solid(block[0], block[1], block[2]) {
+            return Some(RayHit {
+                x:          block[0],
+                y:          block[1],
+                z:          block[2],
+                normal:     normal,
+                distance:   distance as f32
+            });
+        }
+
+        // the closest boundary
+        let a = if next[0] < next[1] {
+            if next[0] < next[2] { 0u } else { 2u }
+        } else {
+            if next[1] < next[2] { 1u } else { 2u }
+        };
+        distance = next[a];
+        if distance > max_distance as f64 {
+            return None;
+        }
+        block[a] += step[a];
+        next[a] += delta[a];
+        normal = match a {
+            0 => (-step[0], 0, 0),
+            1 => (0, -step[1], 0),
+            _ => (0, 0, -step[2])
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use math::Vec3;
+    use super::raycast;
+
+    fn approx(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    fn origin() -> Vec3<f32> {
+        Vec3::new(0.5f32, 0.5f32, 0.5f32)
+    }
+
+    #[test]
+    fn hits_along_x_in_both_directions() {
+        let hit = raycast(&origin(), &Vec3::new(1f32, 0f32, 0f32), 10f32, |x, y, z| (x, y, z) == (3, 0, 0)).unwrap();
+        assert_eq!((hit.x, hit.y, hit.z), (3, 0, 0));
+        assert!(approx(hit.distance, 2.5f32));
+
+        let hit = raycast(&origin(), &Vec3::new(-1f32, 0f32, 0f32), 10f32, |x, y, z| (x, y, z) == (-3, 0, 0)).unwrap();
+        assert_eq!((hit.x, hit.y, hit.z), (-3, 0, 0));
+        assert!(approx(hit.distance, 2.5f32));
+    }
+
+    #[test]
+    fn normal_of_each_face() {
+        let cases = [
+            ((1f32, 0f32, 0f32), (2, 0, 0), (-1, 0, 0)),
+            ((-1f32, 0f32, 0f32), (-2, 0, 0), (1, 0, 0)),
+            ((0f32, 1f32, 0f32), (0, 2, 0), (0, -1, 0)),
+            ((0f32, -1f32, 0f32), (0, -2, 0), (0, 1, 0)),
+            ((0f32, 0f32, 1f32), (0, 0, 2), (0, 0, -1)),
+            ((0f32, 0f32, -1f32), (0, 0, -2), (0, 0, 1))
+        ];
+        for &((dx, dy, dz), block, normal) in cases.iter() {
+            let hit = raycast(&origin(), &Vec3::new(dx, dy, dz), 10f32, |x, y, z| (x, y, z) == block).unwrap();
+            assert_eq!((hit.x, hit.y, hit.z), block);
+            assert_eq!(hit.normal, normal);
+            assert!(approx(hit.distance, 1.5f32));
+        }
+    }
+
+    #[test]
+    fn distance_on_a_diagonal() {
+        // crosses y = 1 first, then reaches x = 3 at y = 1.75
+        let hit = raycast(&origin(), &Vec3::new(2f32, 1f32, 0f32), 10f32, |x, _, _| x >= 3).unwrap();
+        assert_eq!((hit.x, hit.y, hit.z), (3, 1, 0));
+        assert_eq!(hit.normal, (-1, 0, 0));
+        assert!(approx(hit.distance, 1.25f32 * 5f32.sqrt()));
+    }
+
+    #[test]
+    fn stops_at_the_reach() {
+        let dir = Vec3::new(1f32, 0f32, 0f32);
+        assert!(raycast(&origin(), &dir, 6f32, |x, _, _| x == 6).is_some());
+        assert!(raycast(&origin(), &dir, 6f32, |x, _, _| x == 7).is_none());
+        assert!(raycast(&origin(), &dir, 6f32, |_, _, _| false).is_none());
+    }
+
+    #[test]
+    fn starts_inside_a_block() {
+        let hit = raycast(&origin(), &Vec3::new(0f32, 0f32, 1f32), 6f32, |x, y, z| (x, y, z) == (0, 0, 0)).unwrap();
+        assert_eq!((hit.x, hit.y, hit.z), (0, 0, 0));
+        assert_eq!(hit.normal, (0, 0, 0));
+        assert!(approx(hit.distance, 0f32));
+        assert_eq!(hit.get_adjacent(), (0, 0, 0));
+    }
+}
@@ -0,0 +1,251 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// Region files group REGION_SIZE x REGION_SIZE chunks:
+//
+//   magic "RCRG" | version: u32
+//   offset table: REGION_SIZE * REGION_SIZE x (offset: u32, length: u32)
+//   deflate compressed chunk payloads
+//
+// An entry with a zero length means the chunk was never saved. All the
+// integers are little endian.
+
+use std::hashmap::{HashMap, HashSet};
+use std::io::File;
+use std::io::fs;
+use std::io;
+use std::task;
+use std::vec;
+
+use extra::flate;
+
+use world::chunk::{Chunk, ChunkPos, div_floor, mod_floor};
+use world::chunk_map::ChunkMap;
+
+pub static REGION_SIZE: i32     = 32;
+static REGION_MAGIC: &'static [u8] = bytes!("RCRG");
+static REGION_VERSION: u32      = 1;
+static HEADER_SIZE: uint        = 8 + 8 * 32 * 32;
+
+struct Region {
+    // compressed payloads, indexed like the offset table
+    entries:    ~[Option<~[u8]>],
+    dirty:      bool
+}
+
+impl Region {
+    fn new() -> Region {
+        Region {
+            entries:    vec::from_fn((REGION_SIZE * REGION_SIZE) as uint, |_| None),
+            dirty:      false
+        }
+    }
+
+    // None if the file does not exist. an invalid file is moved aside, the
+    // next flush would overwrite the chunks it may still hold
+    fn load(path: &Path) -> Option<Region> {
+        if !path.exists() {
+            return None;
+        }
+        let bytes = match File::open(path) {
+            Some(mut f) => f.read_to_end(),
+            None        => fail!("Cannot read region file: {}", path.display())
+        };
+        if bytes.len() < HEADER_SIZE 
+            || bytes.slice(0, 4) != REGION_MAGIC 
+            || read_u32(bytes.as_slice(), 4) != REGION_VERSION {
+            let aside = path.with_extension("corrupt");
+            println!("Invalid region file: {}, moved to {}", path.display(), aside.display());
+            if !rename(path, &aside) {
+                fail!("Cannot move the invalid region file {} aside", path.display());
+            }
+            return None;
+        }
+
+        let mut region = Region::new();
+        for i in range(0, region.entries.len()) {
+            let offset = read_u32(bytes.as_slice(), 8 + i * 8) as uint;
+            let length = read_u32(bytes.as_slice(), 12 + i * 8) as uint;
+            if length != 0 && offset + length <= bytes.len() {
+                region.entries[i] = Some(bytes.slice(offset, offset + length).to_owned());
+            }
+        }
+        Some(region)
+    }
+
+    fn write(&mut self, path: &Path) -> () {
+        let mut header = REGION_MAGIC.to_owned();
+        let mut payloads: ~[u8] = ~[];
+        push_u32(&mut header, REGION_VERSION);
+        for e in self.entries.iter() {
+            match *e {
+                Some(ref data)  => {
+                    push_u32(&mut header, (HEADER_SIZE + payloads.len()) as u32);
+                    push_u32(&mut header, data.len() as u32);
+                    payloads.push_all(data.as_slice());
+                }
+                None            => {
+                    push_u32(&mut header, 0);
+                    push_u32(&mut header, 0);
+                }
+            }
+        }
+
+        // write next to the old file then swap, a crash can't corrupt the region
+        let tmp = path.with_extension("tmp");
+        match File::create(&tmp) {
+            Some(mut f) => {
+                f.write(header.as_slice());
+                f.write(payloads.as_slice());
+            }
+            None        => fail!("Cannot write region file: {}", tmp.display())
+        }
+        // still dirty on failure, the next flush tries again
+        if rename(&tmp, path) {
+            self.dirty = false;
+        } else {
+            println!("Cannot replace region file: {}", path.display());
+        }
+    }
+}
+
+pub struct RegionStore {
+    priv path:      Path,
+    priv regions:   HashMap<(i32, i32), Region>
+}
+
+impl RegionStore {
+    // path is the directory holding the region files
+    pub fn new(path: Path) -> RegionStore {
+        if !path.exists() {
+            fs::mkdir_recursive(&path, io::UserRWX);
+        }
+        RegionStore {
+            path:       path,
+            regions:    HashMap::new()
+        }
+    }
+
+    pub fn load_chunk(&mut self, position: &ChunkPos) -> Option<~Chunk> {
+        let index = entry_index(position);
+        let region = self.get_region(position);
+        let data = match region.entries[index] {
+            Some(ref data)  => data.clone(),
+            None            => return None
+        };
+        // a damaged chunk is generated again rather than crashing the game
+        let inflated = if data.is_empty() {
+            None
+        } else {
+            // inflate_bytes fails the task on bad data
+            task::try(proc() { flate::inflate_bytes(data.as_slice()) }).ok()
+        };
+        let chunk = match inflated {
+            Some(bytes) => Chunk::decode(position.clone(), bytes.as_slice()),
+            None        => None
+        };
+        if chunk.is_none() {
+            println!("Invalid chunk {} in its region file, generated again", position.to_str());
+        }
+        chunk
+    }
+
+    // the region file is only written by flush()
+    pub fn save_chunk(&mut self, chunk: &Chunk) -> () {
+        let position = chunk.get_position();
+        let index = entry_index(&position);
+        let region = self.get_region(&position);
+        region.entries[index] = Some(flate::deflate_bytes(chunk.encode().as_slice()));
+        region.dirty = true;
+    }
+
+    pub fn flush(&mut self) -> () {
+        for (&(rx, rz), region) in self.regions.mut_iter() {
+            if region.dirty {
+                region.write(&self.path.join(region_file_name(rx, rz)));
+            }
+        }
+    }
+
+    // forgets the saved regions holding none of the loaded chunks
+    pub fn release_unused(&mut self, chunks: &ChunkMap) -> () {
+        let mut used = HashSet::new();
+        for (pos, _) in chunks.iter() {
+            used.insert(region_of(pos));
+        }
+        let mut unused = ~[];
+        for (key, region) in self.regions.iter() {
+            if !region.dirty && !used.contains(key) {
+                unused.push(*key);
+            }
+        }
+        for key in unused.iter() {
+            self.regions.remove(key);
+        }
+    }
+
+    fn get_region<'a>(&'a mut self, position: &ChunkPos) -> &'a mut Region {
+        let (rx, rz) = region_of(position);
+        let path = self.path.join(region_file_name(rx, rz));
+        self.regions.find_or_insert_with((rx, rz), |_| {
+            match Region::load(&path) {
+                Some(r) => r,
+                None    => Region::new()
+            }
+        })
+    }
+}
+
+fn region_of(position: &ChunkPos) -> (i32, i32) {
+    (div_floor(position.x, REGION_SIZE), div_floor(position.z, REGION_SIZE))
+}
+
+// false if the file could not be moved, the save files all go through it
+pub fn rename(from: &Path, to: &Path) -> bool {
+    let mut done = true;
+    io::io_error::cond.trap(|e| {
+        println!("Cannot rename {} to {}: {}", from.display(), to.display(), e.to_str());
+        done = false;
+    }).inside(|| fs::rename(from, to));
+    done
+}
+
+fn region_file_name(rx: i32, rz: i32) -> ~str {
+    format!("r.{}.{}.region", rx, rz)
+}
+
+fn entry_index(position: &ChunkPos) -> uint {
+    (mod_floor(position.z, REGION_SIZE) * REGION_SIZE + mod_floor(position.x, REGION_SIZE)) as uint
+}
+
+fn push_u32(bytes: &mut ~[u8], value: u32) -> () {
+    bytes.push((value & 0xff) as u8);
+    bytes.push(((value >> 8) & 0xff) as u8);
+    bytes.push(((value >> 16) & 0xff) as u8);
+    bytes.push(((value >> 24) & 0xff) as u8);
+}
+
+fn read_u32(bytes: &[u8], offset: uint) -> u32 {
+    (bytes[offset] as u32)
+        | (bytes[offset + 1] as u32 << 8)
+        | (bytes[offset + 2] as u32 << 16)
+        | (bytes[offset + 3] as u32 << 24)
+}
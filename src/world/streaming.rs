@@ -0,0 +1,134 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::num::sqrt;
+use std::cmp::{Less, Equal, Greater};
+
+use math::Vec3;
+use world::chunk::{ChunkPos, CHUNK_SIZE};
+use world::chunk_map::ChunkMap;
+
+pub static DEFAULT_RENDER_DISTANCE: i32 = 6;
+// chunks generated or loaded per world update
+pub static CHUNKS_PER_UPDATE: uint      = 2;
+
+pub struct ChunkStreamer {
+    priv render_distance:   i32,
+    priv center:            Option<ChunkPos>,
+    // missing chunks, the next one to load is at the end
+    priv queue:             ~[ChunkPos]
+}
+
+impl ChunkStreamer {
+    pub fn new(render_distance: i32) -> ChunkStreamer {
+        ChunkStreamer {
+            render_distance:    render_distance,
+            center:             None,
+            queue:              ~[]
+        }
+    }
+
+    pub fn get_render_distance(&self) -> i32 {
+        self.render_distance
+    }
+
+    pub fn set_render_distance(&mut self, render_distance: i32) -> () {
+        self.render_distance = render_distance;
+        self.center = None;
+    }
+
+    // Refresh the load queue around the camera, returns the loaded chunks
+    // which are now out of range.
+    pub fn update(&mut self,
+        chunks: &ChunkMap,
+        position: &Vec3<f32>,
+        direction: &Vec3<f32>) -> ~[ChunkPos] {
+
+        let center = ChunkPos::from_block(position.x.floor() as i32, position.z.floor() as i32);
+        let r = self.render_distance;
+        let mut unload = ~[];
+
+        if self.center != Some(center.clone()) {
+            // one chunk of margin so going back and forth over a border
+            // doesn't reload the same chunks
+            for (pos, _) in chunks.iter() {
+                if !in_range(&center, pos, r + 1) {
+                    unload.push(pos.clone());
+                }
+            }
+            self.queue = ~[];
+            for x in range(center.x - r, center.x + r + 1) {
+                for z in range(center.z - r, center.z + r + 1) {
+                    let pos = ChunkPos::new(x, z);
+                    if in_range(&center, &pos, r) && !chunks.contains(&pos) {
+                        self.queue.push(pos);
+                    }
+                }
+            }
+            self.center = Some(center);
+        }
+
+        // the camera may have turned, sort every update
+        let mut dir_x = direction.x;
+        let mut dir_z = direction.z;
+        let len = sqrt(dir_x * dir_x + dir_z * dir_z);
+        if len > 0.0001 {
+            dir_x /= len;
+            dir_z /= len;
+        }
+        self.queue.sort_by(|a, b| {
+            let sa = priority(a, position, dir_x, dir_z);
+            let sb = priority(b, position, dir_x, dir_z);
+            if sa > sb { Less } else if sa < sb { Greater } else { Equal }
+        });
+
+        unload
+    }
+
+    pub fn next(&mut self) -> Option<ChunkPos> {
+        self.queue.pop()
+    }
+
+    pub fn pending(&self) -> uint {
+        self.queue.len()
+    }
+}
+
+// lower is loaded first
+fn priority(chunk: &ChunkPos, position: &Vec3<f32>, dir_x: f32, dir_z: f32) -> f32 {
+    let half = CHUNK_SIZE as f32 / 2f32;
+    let dx = (chunk.origin_x() as f32 + half - position.x) / CHUNK_SIZE as f32;
+    let dz = (chunk.origin_z() as f32 + half - position.z) / CHUNK_SIZE as f32;
+    let dist = sqrt(dx * dx + dz * dz);
+    if dist < 0.0001 {
+        0f32
+    } else {
+        // chunks behind the camera count as twice farther
+        let facing = (dx * dir_x + dz * dir_z) / dist;
+        dist * (1.5f32 - facing * 0.5f32)
+    }
+}
+
+pub fn in_range(center: &ChunkPos, position: &ChunkPos, distance: i32) -> bool {
+    let dx = position.x - center.x;
+    let dz = position.z - center.z;
+    dx * dx + dz * dz <= distance * distance
+}
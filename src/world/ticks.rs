@@ -0,0 +1,225 @@
+// The MIT License (MIT)
+//
+// Copyright (c) 2014 Jeremy Letang
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+// Block ticks, run on every world tick.
+//  - scheduled ticks: a block asks to be updated at a given tick, e.g. the
+//    fluids to flow or the sand to fall. Changing a block schedules it and
+//    its six neighbours for the next tick. At most MAX_SCHEDULED_PER_TICK
+//    run per tick, the late ones wait for the next ticks.
+//    The queue is not saved: a loaded chunk schedules its blocks which may
+//    still move, see schedule_chunk.
+//  - random ticks: a few random blocks of each loaded chunk, e.g. the
+//    grass spreading on the dirt.
+
+use std::cmp::max;
+use std::hashmap::HashMap;
+
+use extra::priority_queue::PriorityQueue;
+
+use math::prng::Prng;
+use world::block::{BlockRegistry, AIR, DIRT, GRASS, SAND, SPONGE};
+use world::chunk::{ChunkPos, CHUNK_SIZE, CHUNK_HEIGHT};
+use world::chunk_map::ChunkMap;
+use world::fluids;
+
+pub static MAX_SCHEDULED_PER_TICK: uint = 512;
+// by default, per chunk and per tick
+pub static DEFAULT_RANDOM_TICKS: uint   = 48;
+static FALL_DELAY: u64                  = 2;
+// grass dies under this light and spreads to the dirt under at least
+// GRASS_SPREAD_LIGHT
+static GRASS_MIN_LIGHT: u8              = 4;
+static GRASS_SPREAD_LIGHT: u8           = 9;
+
+static AROUND: [(i32, i32, i32), ..6]   = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+
+#[deriving(Eq)]
+struct ScheduledTick {
+    due:        u64,
+    // scheduling order, to run the ticks due together in that order
+    order:      u64,
+    position:   (i32, i32, i32)
+}
+
+// the queue pops the greatest first, so the earliest is the greatest
+impl Ord for ScheduledTick {
+    fn lt(&self, other: &ScheduledTick) -> bool {
+        (self.due, self.order) > (other.due, other.order)
+    }
+}
+
+pub struct TickScheduler {
+    priv queue:     PriorityQueue<ScheduledTick>,
+    // the due tick of each position in the queue, a position only runs once
+    priv pending:   HashMap<(i32, i32, i32), u64>,
+    priv order:     u64
+}
+
+impl TickScheduler {
+    pub fn new() -> TickScheduler {
+        TickScheduler {
+            queue:      PriorityQueue::new(),
+            pending:    HashMap::new(),
+            order:      0
+        }
+    }
+
+    // keeps the earliest when the position is already scheduled
+    pub fn schedule(&mut self, x: i32, y: i32, z: i32, due: u64) -> () {
+        let position = (x, y, z);
+        match self.pending.find(&position) {
+            Some(&d) if d <= due    => return,
+            _                       => {}
+        }
+        self.pending.insert(position, due);
+        self.order += 1;
+        self.queue.push(ScheduledTick { due: due, order: self.order, position: position });
+    }
+
+    pub fn schedule_around(&mut self, x: i32, y: i32, z: i32, due: u64) -> () {
+        self.schedule(x, y, z, due);
+        for &(dx, dy, dz) in AROUND.iter() {
+            self.schedule(x + dx, y + dy, z + dz, due);
+        }
+    }
+
+    pub fn len(&self) -> uint {
+        self.pending.len()
+    }
+
+    // next position due at `now`
+    pub fn pop_due(&mut self, now: u64) -> Option<(i32, i32, i32)> {
+        loop {
+            let due = match self.queue.maybe_top() {
+                Some(t) if t.due <= now => t.due,
+                _                       => return None
+            };
+            let tick = self.queue.pop();
+            // rescheduled earlier, this entry is stale
+            if self.pending.find(&tick.position) == Some(&due) {
+                self.pending.remove(&tick.position);
+                return Some(tick.position);
+            }
+        }
+    }
+}
+
+pub fn scheduled_tick(chunks: &mut ChunkMap, ticks: &mut TickScheduler, x: i32, y: i32, z: i32,
+    now: u64, changed: &mut ~[(i32, i32, i32)]) -> () {
+    let block = chunks.get_block(x, y, z);
+    if fluids::is_fluid(block) {
+        fluids::update(chunks, ticks, x, y, z, now, changed);
+    } else if block == SPONGE {
+        fluids::absorb(chunks, ticks, x, y, z, now, changed);
+    } else if block == SAND {
+        fall(chunks, ticks, x, y, z, now, changed);
+    }
+}
+
+// the fluids and the sand of a freshly loaded chunk which may still move,
+// e.g. a flow stopped by a save or by the chunk being unloaded
+pub fn schedule_chunk(chunks: &ChunkMap, ticks: &mut TickScheduler, position: &ChunkPos, due: u64) -> () {
+    let chunk = match chunks.get_chunk(position) {
+        Some(c) => c,
+        None    => return
+    };
+    let (ox, oz) = (position.origin_x(), position.origin_z());
+    for y in range(0, CHUNK_HEIGHT) {
+        for z in range(0, CHUNK_SIZE) {
+            for x in range(0, CHUNK_SIZE) {
+                let block = chunk.get(x, y, z);
+                let (wx, wz) = (ox + x, oz + z);
+                let unsettled = if fluids::is_fluid(block) {
+                    chunk.get_level(x, y, z) != fluids::SOURCE
+                        || AROUND.iter().any(|&(dx, dy, dz)| {
+                            let other = chunks.get_block(wx + dx, y + dy, wz + dz);
+                            dy <= 0 && (other == AIR || (fluids::is_fluid(other) && other != block))
+                        })
+                } else if block == SAND {
+                    let below = chunks.get_block(wx, y - 1, wz);
+                    y > 0 && (below == AIR || fluids::is_fluid(below))
+                } else {
+                    false
+                };
+                if unsettled {
+                    ticks.schedule(wx, y, wz, due);
+                }
+            }
+        }
+    }
+}
+
+pub fn random_tick(chunks: &mut ChunkMap, blocks: &BlockRegistry, rng: &mut Prng,
+    x: i32, y: i32, z: i32, changed: &mut ~[(i32, i32, i32)]) -> () {
+    if chunks.get_block(x, y, z) == GRASS {
+        spread_grass(chunks, blocks, rng, x, y, z, changed);
+    }
+}
+
+// a random block of each chunk, `count` times
+pub fn random_positions(chunks: &ChunkMap, rng: &mut Prng, count: uint) -> ~[(i32, i32, i32)] {
+    let mut positions = ~[];
+    for (pos, _) in chunks.iter() {
+        for _ in range(0, count) {
+            positions.push((pos.origin_x() + rng.gen_range(0, CHUNK_SIZE),
+                rng.gen_range(0, CHUNK_HEIGHT),
+                pos.origin_z() + rng.gen_range(0, CHUNK_SIZE)));
+        }
+    }
+    positions
+}
+
+// the sand falls one block per FALL_DELAY ticks through the air and the fluids
+fn fall(chunks: &mut ChunkMap, ticks: &mut TickScheduler, x: i32, y: i32, z: i32,
+    now: u64, changed: &mut ~[(i32, i32, i32)]) -> () {
+    let below = chunks.get_block(x, y - 1, z);
+    if y <= 0 || (below != AIR && !fluids::is_fluid(below)) {
+        return;
+    }
+    let block = chunks.get_block(x, y, z);
+    if chunks.set_block(x, y - 1, z, block) && chunks.set_block(x, y, z, AIR) {
+        changed.push((x, y - 1, z));
+        changed.push((x, y, z));
+        ticks.schedule_around(x, y - 1, z, now + FALL_DELAY);
+        ticks.schedule_around(x, y, z, now + 1);
+    }
+}
+
+// grass under an opaque block or in the dark dies, otherwise it spreads to
+// a dirt block nearby which has enough light above
+fn spread_grass(chunks: &mut ChunkMap, blocks: &BlockRegistry, rng: &mut Prng,
+    x: i32, y: i32, z: i32, changed: &mut ~[(i32, i32, i32)]) -> () {
+    let lit = |chunks: &ChunkMap, x: i32, y: i32, z: i32, min_light: u8| -> bool {
+        let above = chunks.get_block(x, y + 1, z);
+        let light = max(chunks.get_sky_light(x, y + 1, z), chunks.get_block_light(x, y + 1, z));
+        blocks.is_transparent(above) && light >= min_light
+    };
+    if !lit(&*chunks, x, y, z, GRASS_MIN_LIGHT) {
+        if chunks.set_block(x, y, z, DIRT) {
+            changed.push((x, y, z));
+        }
+        return;
+    }
+    let (tx, ty, tz) = (x + rng.gen_range(-1, 2), y + rng.gen_range(-3, 2), z + rng.gen_range(-1, 2));
+    if chunks.get_block(tx, ty, tz) == DIRT && lit(&*chunks, tx, ty, tz, GRASS_SPREAD_LIGHT) && chunks.set_block(tx, ty, tz, GRASS) {
+        changed.push((tx, ty, tz));
+    }
+}